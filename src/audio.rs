@@ -0,0 +1,170 @@
+//! Sound effects played by name, with volume settings.
+//!
+//! Every sound is loaded once at startup. Systems send a [`PlaySound`]
+//! with a [`SoundCue`] instead of loading files themselves, and
+//! [`play_sounds`] plays it at the volume picked in the settings.
+
+use bevy::{
+    prelude::*,
+    utils::HashMap,
+};
+use serde::{Serialize, Deserialize};
+
+use super::save::SaveData;
+
+// Constants
+const VOLUME_STEP: f32 = 0.1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SoundCue {
+    Jump,
+    Point,
+    GameOver,
+}
+
+impl SoundCue {
+    pub const ALL: [SoundCue; 3] = [
+        SoundCue::Jump,
+        SoundCue::Point,
+        SoundCue::GameOver,
+    ];
+
+    pub fn path(&self) -> &'static str {
+        match self {
+            SoundCue::Jump => "sounds/jump.mp3",
+            SoundCue::Point => "sounds/point.mp3",
+            SoundCue::GameOver => "sounds/game_over.mp3",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VolumeChannel {
+    Master,
+    Sfx,
+    Music,
+}
+
+impl VolumeChannel {
+    pub const ALL: [VolumeChannel; 3] = [
+        VolumeChannel::Master,
+        VolumeChannel::Sfx,
+        VolumeChannel::Music,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            VolumeChannel::Master => "Master",
+            VolumeChannel::Sfx => "Sound effects",
+            VolumeChannel::Music => "Music",
+        }
+    }
+}
+
+/// Volumes from 0 to 1. Sound effects and music are scaled by the master
+/// volume.
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct AudioSettings {
+    pub master: f32,
+    pub sfx: f32,
+    pub music: f32,
+    pub muted: bool,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        AudioSettings {
+            master: 1.,
+            sfx: 1.,
+            music: 1.,
+            muted: false,
+        }
+    }
+}
+
+impl AudioSettings {
+    pub fn volume(&self, channel: VolumeChannel) -> f32 {
+        match channel {
+            VolumeChannel::Master => self.master,
+            VolumeChannel::Sfx => self.sfx,
+            VolumeChannel::Music => self.music,
+        }
+    }
+
+    /// Moves a channel's volume up or down by `steps` tenths.
+    pub fn step_volume(&mut self, channel: VolumeChannel, steps: i32) {
+        let volume = match channel {
+            VolumeChannel::Master => &mut self.master,
+            VolumeChannel::Sfx => &mut self.sfx,
+            VolumeChannel::Music => &mut self.music,
+        };
+
+        // Rounded so repeated steps land on whole tenths.
+        *volume = ((*volume + steps as f32 * VOLUME_STEP) * 10.).round().clamp(0., 10.) / 10.;
+    }
+
+    /// What sound effects play at, taking master volume and mute into
+    /// account.
+    pub fn sfx_volume(&self) -> f32 {
+        if self.muted { 0. } else { self.master * self.sfx }
+    }
+
+    /// What music plays at, taking master volume and mute into account.
+    pub fn music_volume(&self) -> f32 {
+        if self.muted { 0. } else { self.master * self.music }
+    }
+}
+
+// Components, Resources, Events
+
+/// Every sound effect, loaded at startup.
+#[derive(Resource, Default)]
+pub struct SoundHandles(HashMap<SoundCue, Handle<AudioSource>>);
+
+impl SoundHandles {
+    pub fn get(&self, cue: SoundCue) -> Handle<AudioSource> {
+        self.0
+            .get(&cue)
+            .expect("Should be loaded in audio::setup")
+            .clone()
+    }
+}
+
+/// Plays a sound effect.
+pub struct PlaySound(pub SoundCue);
+
+// Initial Setup
+pub fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    save_data: Res<SaveData>,
+) {
+    let handles = SoundCue::ALL
+        .into_iter()
+        .map(|cue| (cue, asset_server.load(cue.path())))
+        .collect();
+
+    commands.insert_resource(SoundHandles(handles));
+    commands.insert_resource(save_data.audio.clone());
+}
+
+pub fn play_sounds(
+    mut play_sound_events: EventReader<PlaySound>,
+    sound_handles: Res<SoundHandles>,
+    audio_settings: Res<AudioSettings>,
+    audio: Res<Audio>,
+) {
+    let volume = audio_settings.sfx_volume();
+
+    for PlaySound(cue) in play_sound_events.iter() {
+        if volume <= 0. {
+            continue;
+        }
+
+        audio.play_with_settings(
+            sound_handles.get(*cue),
+            PlaybackSettings::ONCE.with_volume(volume),
+        );
+    }
+}
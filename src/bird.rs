@@ -1,57 +1,65 @@
 use bevy::prelude::*;
-use bevy::sprite::collide_aabb::collide;
 
 use super::{
     Velocity, 
-    TIME_STEP,
-    GRAVITY,
     Collider,
     AppState,
     game_ui::scoreboard::Scoreboard,
     pipes::Pipe,
     pipes::PointMarker,
     pipes::BeenAdded,
-    pipes::PIPE_X_SIZE,
-    pipes::PIPE_Y_SIZE,
+    audio::PlaySound,
+    audio::SoundCue,
+    camera::PLAYFIELD_HEIGHT,
+    collision::Sweep,
+    input::Action,
+    input::ActionInput,
+    tuning::Tuning,
 };
 
 // Constants
-const BIRD_SIZE: f32 = 80.;
-const BIRD_SCALE: Vec3 = Vec3::new(BIRD_SIZE, BIRD_SIZE, 1.);
-const BIRD_JUMP: f32 = 800.;
-const BIRD_STARTING_POSITION: Vec3 = Vec3::new(0., 0., 2.);
-const BIRD_COLOR: Color = Color::rgb(0.8, 0.8, 0.2);
+pub const BIRD_SPRITE: &str = "sprites/FlappyQube.png";
+pub const BIRD_TEXTURE_SIZE: Vec2 = Vec2::new(20., 20.);
+const BIRD_SCALE: Vec3 = Vec3::new(4., 4., 0.);
 
-const GRAVITY_CAP: f32 = -70.;
-const SPEED_CAP: Vec2 = Vec2::new(0., 1500. * TIME_STEP);
+const BIRD_STARTING_POSITION: Vec3 = Vec3::new(0., 0., 2.);
 
 // Initial Setup
 pub fn setup(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
+    tuning: Res<Tuning>,
 ) {
-    let bird_handle = asset_server.load("sprites/FlappyQube.png");
-
     commands.spawn((
-        SpriteBundle {
-            texture: bird_handle,
-            transform: Transform {
-                translation: BIRD_STARTING_POSITION,
-                scale: Vec3::new(4., 4., 0.),
-                ..default()
-            },
+        SpatialBundle::from_transform(Transform {
+            translation: BIRD_STARTING_POSITION,
+            scale: BIRD_SCALE,
             ..default()
-        },
+        }),
 
         super::Velocity(Vec2::new(0., 0.)),
+        Collider::circle_from_texture(BIRD_TEXTURE_SIZE, BIRD_SCALE),
 
-        GravityCap(GRAVITY_CAP),
-        SpeedCap(SPEED_CAP),
+        GravityCap(tuning.gravity_cap),
+        SpeedCap(speed_cap(&tuning)),
 
         Bird,
     ));
 }
 
+// Gives the bird its sprite once it has been spawned by setup
+pub fn add_sprite(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    query: Query<Entity, Added<Bird>>,
+) {
+    for bird in &query {
+        commands.entity(bird).insert((
+            Sprite::default(),
+            asset_server.load::<Image, _>(BIRD_SPRITE),
+        ));
+    }
+}
+
 // Components, Resources, Events
 #[derive(Component)]
 pub struct Bird;
@@ -62,8 +70,55 @@ pub struct GravityCap(f32);
 #[derive(Component, Deref, DerefMut)]
 pub struct SpeedCap(Vec2);
 
-#[derive(Default)]
-pub struct BirdCollisionEvent;
+fn speed_cap(tuning: &Tuning) -> Vec2 {
+    Vec2::new(0., tuning.speed_cap)
+}
+
+fn jump_velocity(tuning: &Tuning) -> f32 {
+    tuning.bird_jump
+}
+
+/// Flaps no fixed tick has used yet. Each tick uses at most one, so a
+/// flap is never lost or counted twice however frames and ticks line up.
+#[derive(Resource, Default)]
+pub struct FlapInput(pub u32);
+
+impl FlapInput {
+    /// Whether the next tick flaps.
+    pub fn is_waiting(&self) -> bool {
+        self.0 > 0
+    }
+
+    /// Uses up the flap of this tick, if there is one.
+    pub fn take(&mut self) -> bool {
+        let waiting = self.is_waiting();
+        self.0 = self.0.saturating_sub(1);
+
+        waiting
+    }
+}
+
+pub struct BirdCollisionEvent {
+    pub cause: DeathCause,
+}
+
+/// What the bird hit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeathCause {
+    Pipe,
+    Ceiling,
+    Floor,
+}
+
+impl DeathCause {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DeathCause::Pipe => "Pipe",
+            DeathCause::Ceiling => "Ceiling",
+            DeathCause::Floor => "Floor",
+        }
+    }
+}
 
 #[derive(Default)]
 pub struct BirdJumpEvent;
@@ -71,49 +126,57 @@ pub struct BirdJumpEvent;
 #[derive(Default)]
 pub struct BirdPointEvent;
 
+// Turns the flap action into a flap for the simulation
+pub fn read_flap_input(
+    action_input: Res<ActionInput>,
+    mut flap_input: ResMut<FlapInput>,
+) {
+    // Uses just_pressed instead of pressed so the fly button 
+    // can't be held down
+    if action_input.just_pressed(Action::Flap) {
+        flap_input.0 += 1;
+    }
+}
+
 // Player movement by adding to birds velocity
 pub fn move_bird(
-    keyboard_input: Res<Input<KeyCode>>,
-    mouse_input: Res<Input<MouseButton>>,
+    mut flap_input: ResMut<FlapInput>,
     mut query: Query<(&mut Velocity, &SpeedCap), With<Bird>>,
-    mut bird_jump_event: EventWriter<BirdJumpEvent>
+    mut bird_jump_event: EventWriter<BirdJumpEvent>,
+    tuning: Res<Tuning>,
 ) {
     let (mut bird_velocity, speed_cap) = query.single_mut();
     
-    // Uses just_pressed instead of pressed so the fly button 
-    // can't be held down
-    if 
-        keyboard_input.just_pressed(KeyCode::Space) ||
-        mouse_input.just_pressed(MouseButton::Left)
-    {
+    if flap_input.take() {
         bird_jump_event.send_default();
 
         // Caps the velocity so spamming doesn't
         // endlessly speed up the player
         if bird_velocity.y < speed_cap.y {
-            bird_velocity.y = BIRD_JUMP * TIME_STEP;
+            bird_velocity.y = jump_velocity(&tuning);
         }
     }
 }
 
 // Apply gravity to player's velocity
 pub fn apply_bird_gravity(
-    mut query: Query<(&mut Velocity, &GravityCap), With<Bird>>
+    mut query: Query<(&mut Velocity, &GravityCap), With<Bird>>,
+    tuning: Res<Tuning>,
 ) {
     let (mut bird_velocity, gravity_cap) = query.single_mut(); 
 
     // Caps the velocity.
     if bird_velocity.y > **gravity_cap {
-        bird_velocity.y += GRAVITY * TIME_STEP;
+        bird_velocity.y += tuning.gravity * tuning.time_step;
     }
 }
 
 pub fn rotate_bird(
-    mut query: Query<(&mut Transform, &Velocity), With<Bird>>,
+    mut query: Query<(&mut Transform, &Velocity, &SpeedCap), With<Bird>>,
 ) {
-    let (mut transform, velocity) = query.single_mut();
+    let (mut transform, velocity, speed_cap) = query.single_mut();
 
-    let mut percentage: f32 = velocity.y / SPEED_CAP.y;
+    let mut percentage: f32 = velocity.y / speed_cap.y;
 
     percentage = percentage.max(-1.0);
     percentage = percentage.min(1.0);
@@ -126,52 +189,70 @@ pub fn rotate_bird(
     transform.rotation = min_rotation.lerp(max_rotation, percentage);
 }
 
-// Check for collisions with pipes
+// Check for collisions with pipes anywhere along this tick's movement
 pub fn bird_pipe_collisions(
-    mut bird_query: Query<&Transform, With<Bird>>, 
-    collider_query: Query<&Transform, (With<Collider>, With<Pipe>)>,
+    mut bird_query: Query<(&Transform, &Velocity, &Collider), With<Bird>>, 
+    collider_query: Query<(&Transform, &Velocity, &Collider), With<Pipe>>,
     mut collision_events: EventWriter<BirdCollisionEvent>,
+    tuning: Res<Tuning>,
 ) {
-    let bird_transform = bird_query.single_mut();
+    let (bird_transform, bird_velocity, bird_collider) = bird_query.single_mut();
 
     // Collision check
     //
-    // Checks the bird with a scale of 1 so that the game
-    // is more forgiving.
-    for pipe_transform in &collider_query {
-        // Collision checking function
-        let collision = collide(
-            bird_transform.translation,
-            Vec2::new(20., 20.),
+    // Shrinks the bird by the leniency so that grazing a pipe
+    // is forgiven.
+    let bird = Sweep::from_velocity(
+        bird_transform.translation,
+        **bird_velocity,
+        bird_collider.shrunk(tuning.hitbox_leniency),
+        tuning.time_step,
+    );
+
+    for (pipe_transform, pipe_velocity, pipe_collider) in &collider_query {
+        let pipe = Sweep::from_velocity(
             pipe_transform.translation,
-            Vec2::new(PIPE_X_SIZE, PIPE_Y_SIZE),
+            **pipe_velocity,
+            *pipe_collider,
+            tuning.time_step,
         );
 
         // If there was a collision send a collision event
-        if collision.is_some() {
-            collision_events.send_default();
+        if bird.hits(&pipe).is_some() {
+            collision_events.send(BirdCollisionEvent {
+                cause: DeathCause::Pipe,
+            });
         }
     }
 }
 
-// Check for collisions with point markers
+// Check for collisions with point markers anywhere along this tick's
+// movement
 pub fn bird_point_collisions(
-    mut bird_query: Query<&Transform, With<Bird>>, 
-    mut point_query: Query<(&Transform, &mut BeenAdded), (With<Collider>, With<PointMarker>)>,
+    mut bird_query: Query<(&Transform, &Velocity, &Collider), With<Bird>>, 
+    mut point_query: Query<(&Transform, &Velocity, &Collider, &mut BeenAdded), With<PointMarker>>,
     mut scoreboard: ResMut<Scoreboard>,
     mut point_event: EventWriter<BirdPointEvent>,
+    tuning: Res<Tuning>,
 ) {
-    let bird_transform = bird_query.single_mut();
+    let (bird_transform, bird_velocity, bird_collider) = bird_query.single_mut();
+
+    let bird = Sweep::from_velocity(
+        bird_transform.translation,
+        **bird_velocity,
+        *bird_collider,
+        tuning.time_step,
+    );
 
-    for (point_transform, mut been_added) in &mut point_query {
-        let collision = collide(
-            bird_transform.translation,
-            bird_transform.scale.truncate(),
+    for (point_transform, point_velocity, point_collider, mut been_added) in &mut point_query {
+        let point = Sweep::from_velocity(
             point_transform.translation,
-            point_transform.scale.truncate(),
+            **point_velocity,
+            *point_collider,
+            tuning.time_step,
         );
 
-        if collision.is_some() && been_added.0 == false {
+        if !been_added.0 && bird.hits(&point).is_some() {
             scoreboard.score += 1;
             been_added.0 = true;
 
@@ -181,29 +262,44 @@ pub fn bird_point_collisions(
 }
 
 
-// Check for collisions with roof or floor
+// Check for collisions with roof or floor, the edges of the play area
 pub fn bird_boundary_collisions(
     query: Query<&Transform, With<Bird>>,
     mut collision_events: EventWriter<BirdCollisionEvent>,
 ) {
     let transform = query.single();
-
-    if 
-        transform.translation.y <= -600. ||
-        transform.translation.y >= 600. 
-    {
-        collision_events.send_default();   
+    let edge = PLAYFIELD_HEIGHT / 2.;
+
+    if transform.translation.y <= -edge {
+        collision_events.send(BirdCollisionEvent {
+            cause: DeathCause::Floor,
+        });
+    } else if transform.translation.y >= edge {
+        collision_events.send(BirdCollisionEvent {
+            cause: DeathCause::Ceiling,
+        });
     }
+}
+
+// Picks up new caps when the tuning changes
+pub fn apply_bird_tuning(
+    mut query: Query<(&mut GravityCap, &mut SpeedCap), With<Bird>>,
+    tuning: Res<Tuning>,
+) {
+    let (mut gravity_cap, mut speed_cap) = query.single_mut();
 
+    **gravity_cap = tuning.gravity_cap;
+    **speed_cap = self::speed_cap(&tuning);
 }
 
 // Apply velocity to birds transform
 pub fn apply_bird_velocity(
-    mut query: Query<(&mut Transform, &Velocity), With<Bird>>
+    mut query: Query<(&mut Transform, &Velocity), With<Bird>>,
+    tuning: Res<Tuning>,
 ) {
     for (mut transform, velocity) in &mut query {
-        transform.translation.x += velocity.x;
-        transform.translation.y += velocity.y;
+        transform.translation.x += velocity.x * tuning.time_step;
+        transform.translation.y += velocity.y * tuning.time_step;
     }
 }
 
@@ -211,22 +307,17 @@ pub fn apply_bird_velocity(
 pub fn game_start(
     mut query: Query<(&mut Velocity, &SpeedCap), With<Bird>>,
     mut next_state: ResMut<NextState<AppState>>,
-    keyboard_input: Res<Input<KeyCode>>,
-    mouse_input: Res<Input<MouseButton>>,
+    mut flap_input: ResMut<FlapInput>,
+    tuning: Res<Tuning>,
 ) {
     let (mut bird_velocity, speed_cap) = query.single_mut();
     
     // This does the same thing as the normal movement system
     // with the added feature of setting the game state to 
     // AppState::InGame.
-    if 
-        keyboard_input.just_pressed(KeyCode::Space) ||
-        mouse_input.just_pressed(MouseButton::Left)
-    {
-        if bird_velocity.y < speed_cap.y {
-            bird_velocity.y = BIRD_JUMP * TIME_STEP;
-            next_state.set(AppState::InGame);
-        }
+    if flap_input.take() && bird_velocity.y < speed_cap.y {
+        bird_velocity.y = jump_velocity(&tuning);
+        next_state.set(AppState::InGame);
     }
 }
 
@@ -234,43 +325,42 @@ pub fn game_start(
 pub fn idle_bird_jump(
     mut query: Query<(&Transform, &mut Velocity), With<Bird>>,
     mut bird_jump_event: EventWriter<BirdJumpEvent>,
+    tuning: Res<Tuning>,
 ) {
     let (transform, mut velocity) = query.single_mut();
 
     if transform.translation.y <= -100. {
-        velocity.y = BIRD_JUMP * TIME_STEP;
+        velocity.y = jump_velocity(&tuning);
         bird_jump_event.send_default();
     }
 }
 
 // Bird Jump Sound
 pub fn bird_jump_sound(
-    bird_jump_event: EventReader<BirdJumpEvent>,
-    asset_server: Res<AssetServer>,
-    audio: Res<Audio>,
+    mut bird_jump_event: EventReader<BirdJumpEvent>,
+    mut play_sound_event: EventWriter<PlaySound>,
 ) {
     if bird_jump_event.is_empty() {
         return;
     }
 
-    let jump_sound = asset_server
-        .load("sounds/jump.mp3");
+    // Events stay readable for two frames, clearing them stops the
+    // sound from playing twice.
+    bird_jump_event.clear();
 
-    audio.play(jump_sound);
+    play_sound_event.send(PlaySound(SoundCue::Jump));
 }
 
 // Bird Point Sound
 pub fn bird_point_sound(
-    bird_point_event: EventReader<BirdPointEvent>,
-    asset_server: Res<AssetServer>,
-    audio: Res<Audio>,
+    mut bird_point_event: EventReader<BirdPointEvent>,
+    mut play_sound_event: EventWriter<PlaySound>,
 ) {
     if bird_point_event.is_empty() {
         return;
     }
 
-    let point_sound = asset_server
-        .load("sounds/point.mp3");
+    bird_point_event.clear();
 
-    audio.play(point_sound);
+    play_sound_event.send(PlaySound(SoundCue::Point));
 }
@@ -1,12 +1,73 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, render::camera::ScalingMode};
+
+// Constants
+/// Size of the play area in world units. The camera always shows all of
+/// it, whatever the size of the window.
+pub const PLAYFIELD_WIDTH: f32 = 1920.;
+pub const PLAYFIELD_HEIGHT: f32 = 1200.;
+
+const LETTERBOX_COLOR: Color = Color::BLACK;
+// Far enough out to cover any window shape.
+const LETTERBOX_SIZE: f32 = PLAYFIELD_WIDTH * 10.;
 
 // Initial Setup
 pub fn setup(
     mut commands: Commands,
 ) {
     // Camera
-    commands.spawn(Camera2dBundle::default());
+    commands.spawn(Camera2dBundle {
+        projection: OrthographicProjection {
+            scaling_mode: ScalingMode::AutoMin {
+                min_width: PLAYFIELD_WIDTH,
+                min_height: PLAYFIELD_HEIGHT,
+            },
+            ..default()
+        },
+        ..default()
+    });
+
+    // Letterbox, covers whatever the camera shows past the play area so
+    // pipes are never seen spawning or despawning.
+    let half_width = PLAYFIELD_WIDTH / 2. + LETTERBOX_SIZE / 2.;
+    let half_height = PLAYFIELD_HEIGHT / 2. + LETTERBOX_SIZE / 2.;
+
+    for offset in [
+        Vec2::new(-half_width, 0.),
+        Vec2::new(half_width, 0.),
+        Vec2::new(0., -half_height),
+        Vec2::new(0., half_height),
+    ] {
+        commands.spawn((SpriteBundle {
+            sprite: Sprite {
+                color: LETTERBOX_COLOR,
+                custom_size: Some(Vec2::splat(LETTERBOX_SIZE)),
+                ..default()
+            },
+            transform: Transform::from_translation(offset.extend(10.)),
+            ..default()
+        },
+        Letterbox,
+        ));
+    }
 }
 
-// Moved to a module despite being barebones
-// for potential future extensibility.
+// Components, Resources, Events
+#[derive(Component)]
+pub struct Letterbox;
+
+/// The part of the world the player sees, left and right. Pipes are
+/// spawned and despawned just outside of it.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct VisibleArea {
+    pub left: f32,
+    pub right: f32,
+}
+
+impl Default for VisibleArea {
+    fn default() -> Self {
+        VisibleArea {
+            left: -PLAYFIELD_WIDTH / 2.,
+            right: PLAYFIELD_WIDTH / 2.,
+        }
+    }
+}
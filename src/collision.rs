@@ -0,0 +1,132 @@
+//! Collisions over a whole tick instead of where things end up.
+//!
+//! Checking only where the bird and a pipe are at the end of a tick misses
+//! them passing through each other when a tick moves them further than
+//! they are wide. Sweeping one shape along the path of the other, relative
+//! to it, catches every overlap during the tick.
+
+use bevy::prelude::*;
+
+use super::Collider;
+
+/// A [`Collider`] moving in a straight line over a tick.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sweep {
+    pub start: Vec2,
+    pub end: Vec2,
+    pub collider: Collider,
+}
+
+impl Sweep {
+    /// A collider at `position` at the end of a tick of `time_step`
+    /// seconds, having moved at `velocity` through it.
+    pub fn from_velocity(position: Vec3, velocity: Vec2, collider: Collider, time_step: f32) -> Self {
+        let end = position.truncate();
+
+        Sweep {
+            start: end - velocity * time_step,
+            end,
+            collider,
+        }
+    }
+
+    /// How far into the tick the two shapes first overlap, from 0 at its
+    /// start to 1 at its end. None when they never do.
+    pub fn hits(&self, other: &Sweep) -> Option<f32> {
+        // Seen from `other` it stands still and this shape's center moves
+        // along a line, which hits whenever the line crosses both shapes
+        // added together.
+        let start = self.start - other.start;
+        let motion = (self.end - other.end) - start;
+
+        match (self.collider, other.collider) {
+            (Collider::Rect { size: a }, Collider::Rect { size: b }) => {
+                line_hits_rect(start, motion, (a + b) / 2.)
+            }
+            (Collider::Circle { radius }, Collider::Rect { size }) |
+            (Collider::Rect { size }, Collider::Circle { radius }) => {
+                line_hits_rounded_rect(start, motion, size / 2., radius)
+            }
+            (Collider::Circle { radius: a }, Collider::Circle { radius: b }) => {
+                line_hits_circle(start, motion, Vec2::ZERO, a + b)
+            }
+        }
+    }
+}
+
+// Where the line from `start` along `motion` first enters the rectangle
+// around the origin, 0 when it starts inside.
+fn line_hits_rect(start: Vec2, motion: Vec2, half_size: Vec2) -> Option<f32> {
+    let mut enter: f32 = 0.;
+    let mut exit: f32 = 1.;
+
+    for axis in 0..2 {
+        if motion[axis] == 0. {
+            // Never moves on this axis, so it has to start inside.
+            if start[axis].abs() >= half_size[axis] {
+                return None;
+            }
+
+            continue;
+        }
+
+        let a = (-half_size[axis] - start[axis]) / motion[axis];
+        let b = (half_size[axis] - start[axis]) / motion[axis];
+
+        enter = enter.max(a.min(b));
+        exit = exit.min(a.max(b));
+    }
+
+    if enter >= exit {
+        return None;
+    }
+
+    Some(enter)
+}
+
+// Same as line_hits_rect for a circle around `center`.
+fn line_hits_circle(start: Vec2, motion: Vec2, center: Vec2, radius: f32) -> Option<f32> {
+    let offset = start - center;
+
+    let c = offset.length_squared() - radius * radius;
+    if c < 0. {
+        return Some(0.);
+    }
+
+    let a = motion.length_squared();
+    if a == 0. {
+        return None;
+    }
+
+    let b = 2. * offset.dot(motion);
+    let discriminant = b * b - 4. * a * c;
+    if discriminant < 0. {
+        return None;
+    }
+
+    let enter = (-b - discriminant.sqrt()) / (2. * a);
+
+    (0. ..=1.).contains(&enter).then_some(enter)
+}
+
+// A rectangle grown by `radius` on every side with round corners, which
+// is what a circle touches while moving around a rectangle. Made out of
+// a wide and a tall rectangle and a circle on each corner, so the first
+// of those the line enters is where it enters the whole shape.
+fn line_hits_rounded_rect(start: Vec2, motion: Vec2, half_size: Vec2, radius: f32) -> Option<f32> {
+    let corners = [
+        Vec2::new(-half_size.x, -half_size.y),
+        Vec2::new(-half_size.x, half_size.y),
+        Vec2::new(half_size.x, -half_size.y),
+        Vec2::new(half_size.x, half_size.y),
+    ];
+
+    [
+        line_hits_rect(start, motion, half_size + Vec2::new(radius, 0.)),
+        line_hits_rect(start, motion, half_size + Vec2::new(0., radius)),
+    ]
+    .into_iter()
+    .chain(corners.map(|corner| line_hits_circle(start, motion, corner, radius)))
+    .flatten()
+    .min_by(f32::total_cmp)
+}
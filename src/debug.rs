@@ -1,33 +1,89 @@
-use bevy::prelude::*;
+//! Developer tools, only built with the `debug` cargo feature so they
+//! never reach players.
+//!
+//! F3 toggles an overlay with collider outlines, velocities and the state
+//! of the game. F5 freezes time, F6 then steps it one fixed tick at a
+//! time and F7/F8 run it from 0.1x to 4x speed. The key under escape
+//! opens a console with commands to set up late game situations, `help`
+//! lists them.
 
-use crate::{
-    Velocity,
-    pipes::*,
+use bevy::{
+    prelude::*,
+    render::view::VisibilitySystems,
 };
 
-const TIME_STEP: f32 = 1.;
+use super::{
+    AppState,
+    SimulationSet,
+    game_over,
+    input,
+    interpolation,
+    replay,
+    tuning::Tuning,
+};
 
-pub fn setup(
-    mut commands: Commands,
-) {
-    commands.spawn(DebugTimer(Timer::from_seconds(1., TimerMode::Repeating)));
-}
+pub mod console;
+pub mod overlay;
+pub mod time_controls;
+
+/// Developer tools on top of [`crate::PresentationPlugin`].
+pub struct DebugPlugin;
+
+impl Plugin for DebugPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<overlay::DebugOverlay>()
+            .init_resource::<time_controls::TimeControls>()
+            .init_resource::<console::DebugConsole>()
+            .init_resource::<console::GodMode>()
+            .init_resource::<console::PendingSeed>()
+
+            .add_startup_system(overlay::setup)
+            .add_startup_system(console::setup)
+
+            .add_systems(
+                (
+                    overlay::toggle_overlay,
+                    overlay::add_outlines,
+                    overlay::update_bird_outline
+                        .after(overlay::add_outlines)
+                        .run_if(resource_changed::<Tuning>()),
+                    overlay::update_debug_text
+                        .run_if(|overlay: Res<overlay::DebugOverlay>| overlay.visible),
+                    overlay::log_points,
+
+                    time_controls::time_controls,
+
+                    console::update_console_text
+                        .run_if(resource_changed::<console::DebugConsole>()),
+                )
+            )
+            .add_system(console::run_commands)
+
+            // Takes the keyboard before the game sees it.
+            .add_system(console::console_input
+                .in_base_set(CoreSet::PreUpdate)
+                .after(bevy::input::InputSystem)
+                .before(input::ActionSystem)
+            )
+            .add_system(console::apply_pending_seed
+                .in_schedule(OnExit(AppState::InGame))
+                .after(game_over::reset_run)
+                .after(replay::end_playback)
+            )
+            .add_system(console::god_mode
+                .before(game_over::game_over)
+                .in_set(SimulationSet::GameOver)
+                .run_if(in_state(AppState::InGame))
+                .run_if(|god_mode: Res<console::GodMode>| god_mode.0)
+                .in_schedule(CoreSchedule::FixedUpdate)
+            )
 
-#[derive(Component)]
-pub struct DebugTimer(Timer);
-
-pub fn log_points(
-    pipe_query: Query<(&Velocity, &Offset, &NumberOf, &BeenAdded), (With<PointMarker>, Changed<BeenAdded>)>,
-) {
-    for (velocity, offset, num, been_added) in &pipe_query {
-        if been_added.0 == true {
-            println!("----------------");
-            println!("Point Num: {:?}", num.0);
-            println!("  Velocity: {:?}", velocity.0);
-            println!("  Offset: {:?}", offset.0);
-            println!("  Been Added: {:?}", been_added.0);
-            println!("----------------");
-            println!("\n");
-        }
+            // Follows what is drawn, after interpolation has moved it.
+            .add_system(overlay::follow_targets
+                .in_base_set(CoreSet::PostUpdate)
+                .after(interpolation::interpolate_transforms)
+                .before(VisibilitySystems::CheckVisibility)
+            );
     }
 }
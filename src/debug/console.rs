@@ -0,0 +1,390 @@
+use bevy::{
+    prelude::*,
+    ecs::system::CommandQueue,
+};
+
+use crate::{
+    AppState,
+    Velocity,
+    bird::{Bird, BirdCollisionEvent},
+    camera::PLAYFIELD_HEIGHT,
+    game_over::UnrankedRun,
+    game_ui::FONT,
+    game_ui::game_over::RunSummary,
+    game_ui::name_entry::PendingEntry,
+    game_ui::scoreboard::Scoreboard,
+    pipes::{self, CourseRng, CourseSpawner, NumberOf},
+    tuning::{BaseTuning, Tuning},
+};
+
+// Constants
+const CONSOLE_TEXT_SIZE: f32 = 20.;
+const CONSOLE_BACKGROUND: Color = Color::rgba(0., 0., 0., 0.8);
+// Lines of history kept on screen.
+const CONSOLE_LINES: usize = 12;
+
+const HELP: &str = "\
+set <name> <value> - Changes a tuning value before the difficulty, the run won't count
+score <score> - Sets the score, the run won't count
+god <on|off> - Pipes, floor and ceiling can't kill the bird, runs won't count
+seed <seed> - Plays this seed from the next run, straight away in menus
+spawn_pipe <x> [height] - Adds a column at x, height of the top pipe, the run won't count
+state <state> - Changes the state, like MainMenu or InGame";
+
+// Tuning values `set` can change, with the field they are stored in.
+const TUNING_NAMES: [&str; 10] = [
+    "time_step",
+    "gravity",
+    "bird_jump",
+    "gravity_cap",
+    "speed_cap",
+    "pipe_gap_x",
+    "pipe_gap_y",
+    "pipe_height_jump",
+    "pipe_speed",
+    "hitbox_leniency",
+];
+
+// Initial Setup
+pub fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+) {
+    commands.spawn((NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                left: Val::Px(0.),
+                top: Val::Px(0.),
+                ..default()
+            },
+            size: Size::width(Val::Percent(100.)),
+            padding: UiRect::all(Val::Px(8.)),
+            ..default()
+        },
+        background_color: CONSOLE_BACKGROUND.into(),
+        visibility: Visibility::Hidden,
+        ..default()
+    },
+    ConsoleNode,
+    ))
+
+    .with_children(|parent| {
+        parent.spawn((TextBundle::from_section(
+            "",
+            TextStyle {
+                font: asset_server.load(FONT),
+                font_size: CONSOLE_TEXT_SIZE,
+                color: crate::TEXT_COLOR,
+            },
+        ),
+
+        ConsoleText,
+        ));
+    });
+}
+
+// Components, Resources, Events
+/// The developer console, opened with the key under escape.
+#[derive(Resource, Default)]
+pub struct DebugConsole {
+    pub open: bool,
+    pub input: String,
+    pub history: Vec<String>,
+    // Entered but not yet run.
+    pending: Vec<String>,
+}
+
+impl DebugConsole {
+    /// Runs `line` as if it was typed in.
+    pub fn run(&mut self, line: impl Into<String>) {
+        self.pending.push(line.into());
+    }
+}
+
+/// Whether the bird can die. Toggled with `god`.
+#[derive(Resource, Default)]
+pub struct GodMode(pub bool);
+
+/// A seed set with `seed` during a run, played from the next one.
+#[derive(Resource, Default)]
+pub struct PendingSeed(pub Option<u64>);
+
+#[derive(Component)]
+pub struct ConsoleNode;
+
+#[derive(Component)]
+pub struct ConsoleText;
+
+// Opens and closes the console and types into it. Keyboard and mouse
+// input is swallowed while it is open so typing doesn't play the game.
+pub fn console_input(
+    mut console: ResMut<DebugConsole>,
+    mut characters: EventReader<ReceivedCharacter>,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    mut mouse_input: ResMut<Input<MouseButton>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Grave) {
+        console.open = !console.open;
+        characters.clear();
+        keyboard_input.reset_all();
+        return;
+    }
+
+    if !console.open {
+        characters.clear();
+        return;
+    }
+
+    for character in characters.iter() {
+        if !character.char.is_control() && character.char != '`' {
+            console.input.push(character.char);
+        }
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Back) {
+        console.input.pop();
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Return) {
+        let line = std::mem::take(&mut console.input);
+        console.run(line);
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        console.open = false;
+    }
+
+    keyboard_input.reset_all();
+    mouse_input.reset_all();
+}
+
+// Runs entered commands with access to the whole world
+pub fn run_commands(world: &mut World) {
+    let pending = std::mem::take(&mut world.resource_mut::<DebugConsole>().pending);
+
+    for line in pending {
+        let output = match run_command(world, &line) {
+            Ok(output) => output,
+            Err(error) => format!("Error: {error}"),
+        };
+
+        info!("Console: {line} -> {output}");
+
+        let mut console = world.resource_mut::<DebugConsole>();
+        console.history.push(format!("> {line}"));
+        console.history.extend(output.lines().map(str::to_string));
+    }
+}
+
+fn run_command(world: &mut World, line: &str) -> Result<String, String> {
+    let mut words = line.split_whitespace();
+
+    let Some(command) = words.next() else {
+        return Ok(String::new());
+    };
+
+    let mut argument = |name: &str| words
+        .next()
+        .map(str::to_string)
+        .ok_or(format!("missing {name}, try help"));
+
+    match command {
+        "help" => Ok(HELP.to_string()),
+
+        "set" => {
+            let name = argument("name")?;
+            let value = parse(&argument("value")?)?;
+
+            let mut tuning = world.resource::<BaseTuning>().0.clone();
+            let field = tuning_field(&mut tuning, &name).ok_or(format!(
+                "unknown value `{name}`, one of {}",
+                TUNING_NAMES.join(", "),
+            ))?;
+            *field = value;
+
+            // The same checks as the tuning file, some values hang the game.
+            tuning.validate().map_err(|error| error.to_string())?;
+            world.resource_mut::<BaseTuning>().0 = tuning;
+
+            // Its replay starts with the old tuning, so it can't play back.
+            if world.resource::<State<AppState>>().0 == AppState::InGame {
+                world.insert_resource(UnrankedRun);
+            }
+
+            Ok(format!("{name} is now {value}"))
+        }
+
+        "score" => {
+            let score = parse(&argument("score")?)?;
+            world.resource_mut::<Scoreboard>().score = score;
+            world.insert_resource(UnrankedRun);
+
+            Ok(format!("Score is now {score}"))
+        }
+
+        "god" => {
+            let on = match argument("on or off")?.as_str() {
+                "on" => true,
+                "off" => false,
+                other => return Err(format!("expected on or off, got `{other}`")),
+            };
+            world.resource_mut::<GodMode>().0 = on;
+            if on {
+                world.insert_resource(UnrankedRun);
+            }
+
+            Ok(format!("God mode {}", if on { "on" } else { "off" }))
+        }
+
+        "seed" => {
+            let seed = parse(&argument("seed")?)?;
+
+            // Changing the course mid run would also stop its replay from
+            // playing back the same.
+            if world.resource::<State<AppState>>().0 == AppState::InGame {
+                world.resource_mut::<PendingSeed>().0 = Some(seed);
+                return Ok(format!("Seed {seed} plays from the next run"));
+            }
+
+            // Pipes don't move outside of a run, so the course on screen
+            // can be swapped for the new one straight away.
+            world.insert_resource(CourseRng::fixed(seed));
+            world.resource_mut::<PendingSeed>().0 = None;
+
+            let pipes: Vec<Entity> = world
+                .query_filtered::<Entity, With<NumberOf>>()
+                .iter(world)
+                .collect();
+            for pipe in pipes {
+                world.despawn(pipe);
+            }
+            *world.resource_mut::<CourseSpawner>() = CourseSpawner::default();
+
+            Ok(format!("Playing seed {seed}"))
+        }
+
+        "spawn_pipe" => {
+            let x = parse(&argument("x")?)?;
+
+            let tuning = world.resource::<Tuning>();
+            let range = &tuning.pipe_height_range;
+            let middle = (range.start() + range.end()) as f32 / 2.;
+            let height = match argument("height") {
+                Ok(height) => parse(&height)?,
+                Err(_) => middle,
+            };
+            let gap_y = tuning
+                .course_at(world.resource::<Scoreboard>().score)
+                .pipe_gap_y;
+
+            let mut queue = CommandQueue::default();
+            pipes::spawn_column(&mut Commands::new(&mut queue, world), 0, x, height, gap_y);
+            queue.apply(world);
+
+            // Replays don't know about columns spawned here.
+            if world.resource::<State<AppState>>().0 == AppState::InGame {
+                world.insert_resource(UnrankedRun);
+            }
+
+            Ok(format!("Spawned a column at x {x}, height {height}"))
+        }
+
+        "state" => {
+            let name = argument("state")?;
+            let state = AppState::variants()
+                .find(|state| format!("{state:?}").eq_ignore_ascii_case(&name))
+                .ok_or(format!("unknown state `{name}`"))?;
+
+            // These screens show a finished run and have nothing to show
+            // before one.
+            if state == AppState::GameOver && !world.contains_resource::<RunSummary>() {
+                return Err("no run has ended yet".to_string());
+            }
+            if state == AppState::NameEntry && world.resource::<PendingEntry>().0.is_none() {
+                return Err("no run is waiting for a name".to_string());
+            }
+
+            world.resource_mut::<NextState<AppState>>().set(state);
+
+            Ok(format!("Going to {state:?}"))
+        }
+
+        _ => Err(format!("unknown command `{command}`, try help")),
+    }
+}
+
+fn parse<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("bad value `{value}`"))
+}
+
+fn tuning_field<'a>(tuning: &'a mut Tuning, name: &str) -> Option<&'a mut f32> {
+    match name {
+        "time_step" => Some(&mut tuning.time_step),
+        "gravity" => Some(&mut tuning.gravity),
+        "bird_jump" => Some(&mut tuning.bird_jump),
+        "gravity_cap" => Some(&mut tuning.gravity_cap),
+        "speed_cap" => Some(&mut tuning.speed_cap),
+        "pipe_gap_x" => Some(&mut tuning.pipe_gap_x),
+        "pipe_gap_y" => Some(&mut tuning.pipe_gap_y),
+        "pipe_height_jump" => Some(&mut tuning.pipe_height_jump),
+        "pipe_speed" => Some(&mut tuning.pipe_speed),
+        "hitbox_leniency" => Some(&mut tuning.hitbox_leniency),
+        _ => None,
+    }
+}
+
+// Plays the seed set during the last run, once it has been reset
+pub fn apply_pending_seed(
+    mut commands: Commands,
+    mut pending_seed: ResMut<PendingSeed>,
+) {
+    if let Some(seed) = pending_seed.0.take() {
+        info!("Course seed: {seed}");
+        commands.insert_resource(CourseRng::fixed(seed));
+    }
+}
+
+// Stops collisions from ending the run and keeps the bird on screen
+pub fn god_mode(
+    mut commands: Commands,
+    mut collision_events: ResMut<Events<BirdCollisionEvent>>,
+    mut bird_query: Query<(&mut Transform, &mut Velocity), With<Bird>>,
+) {
+    collision_events.clear();
+
+    // Turning god mode off doesn't make the rest of the run fair.
+    commands.insert_resource(UnrankedRun);
+
+    let (mut transform, mut velocity) = bird_query.single_mut();
+
+    // Just inside the edges, which count as the floor and ceiling.
+    let edge = PLAYFIELD_HEIGHT / 2. - 1.;
+    if transform.translation.y.abs() > edge {
+        transform.translation.y = transform.translation.y.clamp(-edge, edge);
+        velocity.y = 0.;
+    }
+}
+
+pub fn update_console_text(
+    console: Res<DebugConsole>,
+    mut node_query: Query<&mut Visibility, With<ConsoleNode>>,
+    mut text_query: Query<&mut Text, With<ConsoleText>>,
+) {
+    *node_query.single_mut() = if console.open {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+
+    let start = console.history.len().saturating_sub(CONSOLE_LINES);
+    let mut text = console.history[start..].join("\n");
+    if !text.is_empty() {
+        text.push('\n');
+    }
+    text.push_str(&format!("> {}_", console.input));
+
+    text_query.single_mut().sections[0].value = text;
+}
@@ -0,0 +1,349 @@
+use std::fmt::Write;
+
+use bevy::{
+    prelude::*,
+    render::mesh::PrimitiveTopology,
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+};
+
+use crate::{
+    AppState,
+    Collider,
+    PauseState,
+    Velocity,
+    bird::Bird,
+    game_ui::FONT,
+    game_ui::scoreboard::Scoreboard,
+    pipes::{BeenAdded, NumberOf, PointMarker},
+    replay::RunTick,
+    tuning::Tuning,
+};
+
+use super::{
+    console::GodMode,
+    time_controls::TimeControls,
+};
+
+// Constants
+// Above the letterbox so things are seen before they scroll into view.
+const OVERLAY_Z: f32 = 20.;
+
+const COLLIDER_COLOR: Color = Color::rgb(1., 0.2, 0.2);
+const MARKER_COLOR: Color = Color::rgb(0.2, 1., 0.2);
+const VELOCITY_COLOR: Color = Color::rgb(1., 0.9, 0.1);
+
+// Seconds of movement a velocity arrow is as long as.
+const VELOCITY_ARROW_SECONDS: f32 = 0.25;
+const CIRCLE_SEGMENTS: usize = 32;
+
+const DEBUG_TEXT_SIZE: f32 = 20.;
+const DEBUG_TEXT_BACKGROUND: Color = Color::rgba(0., 0., 0., 0.6);
+
+// Initial Setup
+pub fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    // A line from the origin to x = 1, stretched and turned to fit each
+    // velocity.
+    let mut line = Mesh::new(PrimitiveTopology::LineList);
+    line.insert_attribute(Mesh::ATTRIBUTE_POSITION, vec![[0., 0., 0.], [1., 0., 0.]]);
+
+    commands.insert_resource(DebugAssets {
+        line: meshes.add(line),
+        collider: materials.add(COLLIDER_COLOR.into()),
+        marker: materials.add(MARKER_COLOR.into()),
+        velocity: materials.add(VELOCITY_COLOR.into()),
+    });
+
+    commands.spawn((TextBundle {
+        visibility: Visibility::Hidden,
+        ..TextBundle::from_section(
+            "",
+            TextStyle {
+                font: asset_server.load(FONT),
+                font_size: DEBUG_TEXT_SIZE,
+                color: crate::TEXT_COLOR,
+            },
+        ).with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                left: Val::Px(8.),
+                bottom: Val::Px(8.),
+                ..default()
+            },
+            ..default()
+        })
+        .with_background_color(DEBUG_TEXT_BACKGROUND)
+    },
+
+    DebugText,
+    ));
+}
+
+// Components, Resources, Events
+/// Whether the debug overlay is shown.
+#[derive(Resource, Default)]
+pub struct DebugOverlay {
+    pub visible: bool,
+}
+
+#[derive(Resource)]
+pub struct DebugAssets {
+    line: Handle<Mesh>,
+    collider: Handle<ColorMaterial>,
+    marker: Handle<ColorMaterial>,
+    velocity: Handle<ColorMaterial>,
+}
+
+/// Draws the collider of `target`.
+#[derive(Component)]
+pub struct ColliderOutline {
+    pub target: Entity,
+}
+
+/// Draws the velocity of `target`.
+#[derive(Component)]
+pub struct VelocityArrow {
+    pub target: Entity,
+}
+
+/// On entities that already have their outline and arrow.
+#[derive(Component)]
+pub struct Outlined;
+
+#[derive(Component)]
+pub struct DebugText;
+
+// The outline of `collider` around the origin.
+fn outline_mesh(collider: &Collider) -> Mesh {
+    let positions: Vec<[f32; 3]> = match *collider {
+        Collider::Circle { radius } => (0..=CIRCLE_SEGMENTS)
+            .map(|segment| {
+                let angle = segment as f32 / CIRCLE_SEGMENTS as f32 * std::f32::consts::TAU;
+                [angle.cos() * radius, angle.sin() * radius, 0.]
+            })
+            .collect(),
+        Collider::Rect { size } => {
+            let half = size / 2.;
+
+            vec![
+                [-half.x, -half.y, 0.],
+                [half.x, -half.y, 0.],
+                [half.x, half.y, 0.],
+                [-half.x, half.y, 0.],
+                [-half.x, -half.y, 0.],
+            ]
+        }
+    };
+
+    let mut mesh = Mesh::new(PrimitiveTopology::LineStrip);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh
+}
+
+// The bird is drawn the size it hits pipes with.
+fn drawn_collider(collider: &Collider, is_bird: bool, tuning: &Tuning) -> Collider {
+    if is_bird {
+        collider.shrunk(tuning.hitbox_leniency)
+    } else {
+        *collider
+    }
+}
+
+// Shows and hides the overlay with F3
+pub fn toggle_overlay(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut overlay: ResMut<DebugOverlay>,
+    mut query: Query<&mut Visibility, Or<(With<ColliderOutline>, With<VelocityArrow>, With<DebugText>)>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::F3) {
+        return;
+    }
+
+    overlay.visible = !overlay.visible;
+
+    let visibility = if overlay.visible {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+
+    for mut entity_visibility in &mut query {
+        *entity_visibility = visibility;
+    }
+}
+
+// Gives every collider an outline and a velocity arrow as it is spawned.
+// They are separate entities so the scale of pipe and bird sprites
+// doesn't stretch them.
+pub fn add_outlines(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    debug_assets: Res<DebugAssets>,
+    overlay: Res<DebugOverlay>,
+    tuning: Res<Tuning>,
+    query: Query<(Entity, &Collider, Option<&Bird>, Option<&PointMarker>), Without<Outlined>>,
+) {
+    let visibility = if overlay.visible {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+
+    for (entity, collider, bird, point_marker) in &query {
+        let material = if point_marker.is_some() {
+            debug_assets.marker.clone()
+        } else {
+            debug_assets.collider.clone()
+        };
+
+        let collider = drawn_collider(collider, bird.is_some(), &tuning);
+
+        commands.spawn((MaterialMesh2dBundle {
+            mesh: meshes.add(outline_mesh(&collider)).into(),
+            material,
+            visibility,
+            ..default()
+        },
+        ColliderOutline { target: entity },
+        ));
+
+        commands.spawn((MaterialMesh2dBundle {
+            mesh: debug_assets.line.clone().into(),
+            material: debug_assets.velocity.clone(),
+            visibility,
+            ..default()
+        },
+        VelocityArrow { target: entity },
+        ));
+
+        commands.entity(entity).insert(Outlined);
+    }
+}
+
+// Redraws the bird's outline when the leniency changes
+pub fn update_bird_outline(
+    mut meshes: ResMut<Assets<Mesh>>,
+    tuning: Res<Tuning>,
+    bird_query: Query<(Entity, &Collider), With<Bird>>,
+    outline_query: Query<(&ColliderOutline, &Mesh2dHandle)>,
+) {
+    let Ok((bird, collider)) = bird_query.get_single() else {
+        return;
+    };
+
+    for (outline, mesh) in &outline_query {
+        if outline.target == bird {
+            let collider = drawn_collider(collider, true, &tuning);
+            let _ = meshes.set(mesh.0.clone(), outline_mesh(&collider));
+        }
+    }
+}
+
+// Moves outlines and arrows to where their entity is drawn, and removes
+// them once it is gone
+pub fn follow_targets(
+    mut commands: Commands,
+    target_query: Query<(&GlobalTransform, Option<&Velocity>), (Without<ColliderOutline>, Without<VelocityArrow>)>,
+    mut outline_query: Query<(Entity, &ColliderOutline, &mut GlobalTransform), Without<VelocityArrow>>,
+    mut arrow_query: Query<(Entity, &VelocityArrow, &mut GlobalTransform), Without<ColliderOutline>>,
+) {
+    for (entity, outline, mut global_transform) in &mut outline_query {
+        let Ok((target, _)) = target_query.get(outline.target) else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+
+        *global_transform = GlobalTransform::from_translation(
+            target.translation().truncate().extend(OVERLAY_Z),
+        );
+    }
+
+    for (entity, arrow, mut global_transform) in &mut arrow_query {
+        let Ok((target, velocity)) = target_query.get(arrow.target) else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+
+        let velocity = velocity.map_or(Vec2::ZERO, |velocity| velocity.0);
+
+        *global_transform = GlobalTransform::from(Transform {
+            translation: target.translation().truncate().extend(OVERLAY_Z),
+            rotation: Quat::from_rotation_z(velocity.y.atan2(velocity.x)),
+            scale: Vec3::new(velocity.length() * VELOCITY_ARROW_SECONDS, 1., 1.),
+        });
+    }
+}
+
+// Lists the state of the game and of every column on screen
+pub fn update_debug_text(
+    app_state: Res<State<AppState>>,
+    pause_state: Res<State<PauseState>>,
+    time: Res<Time>,
+    time_controls: Res<TimeControls>,
+    scoreboard: Res<Scoreboard>,
+    run_tick: Res<RunTick>,
+    god_mode: Res<GodMode>,
+    bird_query: Query<(&Transform, &Velocity), With<Bird>>,
+    column_query: Query<(&NumberOf, &BeenAdded, &Transform), With<PointMarker>>,
+    mut text_query: Query<&mut Text, With<DebugText>>,
+) {
+    let mut text = String::new();
+
+    let _ = writeln!(text, "State: {:?} ({:?})", app_state.0, pause_state.0);
+    let _ = writeln!(text, "Score: {}  Tick: {}", scoreboard.score, run_tick.0);
+    if god_mode.0 {
+        let _ = writeln!(text, "God mode");
+    }
+    let _ = writeln!(
+        text,
+        "Time: {}x{}",
+        time_controls.speed(),
+        if time.is_paused() { ", frozen (F6 to step)" } else { "" },
+    );
+
+    if let Ok((transform, velocity)) = bird_query.get_single() {
+        let _ = writeln!(
+            text,
+            "Bird: y {:.1}  velocity {:.1}",
+            transform.translation.y,
+            velocity.y,
+        );
+    }
+
+    let mut columns: Vec<_> = column_query.iter().collect();
+    columns.sort_by_key(|(number, _, _)| number.0);
+
+    let _ = write!(text, "Columns:");
+    for (number, been_added, transform) in columns {
+        let _ = write!(
+            text,
+            "\n  #{}  x {:.0}  {}",
+            number.0,
+            transform.translation.x,
+            if been_added.0 { "scored" } else { "-" },
+        );
+    }
+
+    text_query.single_mut().sections[0].value = text;
+}
+
+// Logs every point as it is scored
+pub fn log_points(
+    point_query: Query<(&Transform, &NumberOf, &BeenAdded), (With<PointMarker>, Changed<BeenAdded>)>,
+    scoreboard: Res<Scoreboard>,
+) {
+    for (transform, number, been_added) in &point_query {
+        if been_added.0 {
+            info!(
+                "Column {} scored at x {:.1}, score {}",
+                number.0,
+                transform.translation.x,
+                scoreboard.score,
+            );
+        }
+    }
+}
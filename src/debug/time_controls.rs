@@ -0,0 +1,68 @@
+use bevy::prelude::*;
+
+// Constants
+const SPEEDS: [f32; 6] = [0.1, 0.25, 0.5, 1., 2., 4.];
+const NORMAL_SPEED: usize = 3;
+
+// Components, Resources, Events
+/// How fast time runs, for the game and everything else.
+#[derive(Resource)]
+pub struct TimeControls {
+    speed: usize,
+}
+
+impl Default for TimeControls {
+    fn default() -> Self {
+        TimeControls {
+            speed: NORMAL_SPEED,
+        }
+    }
+}
+
+impl TimeControls {
+    /// Speed as a multiple of real time.
+    pub fn speed(&self) -> f32 {
+        SPEEDS[self.speed]
+    }
+}
+
+// F5 freezes and unfreezes time, F6 runs exactly one fixed tick while
+// frozen, F7 and F8 slow time down and speed it up
+pub fn time_controls(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut controls: ResMut<TimeControls>,
+    mut time: ResMut<Time>,
+    mut fixed_time: ResMut<FixedTime>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F5) {
+        if time.is_paused() {
+            time.unpause();
+        } else {
+            time.pause();
+        }
+    }
+
+    // A frozen clock adds nothing to the fixed schedule, so the one
+    // period added here is the only tick it runs.
+    if keyboard_input.just_pressed(KeyCode::F6) {
+        time.pause();
+
+        let period = fixed_time.period;
+        fixed_time.tick(period);
+    }
+
+    let speed = if keyboard_input.just_pressed(KeyCode::F7) {
+        controls.speed.saturating_sub(1)
+    } else if keyboard_input.just_pressed(KeyCode::F8) {
+        (controls.speed + 1).min(SPEEDS.len() - 1)
+    } else {
+        return;
+    };
+
+    if speed != controls.speed {
+        controls.speed = speed;
+        time.set_relative_speed(controls.speed());
+
+        info!("Time runs at {}x", controls.speed());
+    }
+}
@@ -0,0 +1,167 @@
+//! Difficulty presets, applied on top of the tuning file.
+
+use std::{fmt, str::FromStr};
+
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+
+use super::{
+    replay::ReplayPlayback,
+    save::SaveData,
+    tuning::{BaseTuning, Tuning},
+};
+
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    Insane,
+}
+
+/// How a difficulty changes the tuning, as multipliers of the tuning
+/// file's values.
+struct DifficultyScale {
+    pipe_gap_x: f32,
+    pipe_gap_y: f32,
+    pipe_speed: f32,
+    gravity: f32,
+    // Spread of pipe heights around the middle of the height range.
+    height_variance: f32,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 4] = [
+        Difficulty::Easy,
+        Difficulty::Normal,
+        Difficulty::Hard,
+        Difficulty::Insane,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+            Difficulty::Insane => "Insane",
+        }
+    }
+
+    /// The next harder difficulty, wrapping around to the easiest.
+    pub fn next(&self) -> Self {
+        let index = Self::ALL
+            .iter()
+            .position(|difficulty| difficulty == self)
+            .expect("Should be in Difficulty::ALL");
+
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// The next easier difficulty, wrapping around to the hardest.
+    pub fn previous(&self) -> Self {
+        let index = Self::ALL
+            .iter()
+            .position(|difficulty| difficulty == self)
+            .expect("Should be in Difficulty::ALL");
+
+        Self::ALL[(index + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+
+    fn scale(&self) -> DifficultyScale {
+        match self {
+            Difficulty::Easy => DifficultyScale {
+                pipe_gap_x: 1.15,
+                pipe_gap_y: 1.4,
+                pipe_speed: 0.8,
+                gravity: 0.85,
+                height_variance: 0.6,
+            },
+            Difficulty::Normal => DifficultyScale {
+                pipe_gap_x: 1.,
+                pipe_gap_y: 1.,
+                pipe_speed: 1.,
+                gravity: 1.,
+                height_variance: 1.,
+            },
+            Difficulty::Hard => DifficultyScale {
+                pipe_gap_x: 0.9,
+                pipe_gap_y: 0.8,
+                pipe_speed: 1.2,
+                gravity: 1.1,
+                height_variance: 1.2,
+            },
+            Difficulty::Insane => DifficultyScale {
+                pipe_gap_x: 0.8,
+                pipe_gap_y: 0.65,
+                pipe_speed: 1.45,
+                gravity: 1.25,
+                height_variance: 1.4,
+            },
+        }
+    }
+
+    /// `base` as played on this difficulty.
+    pub fn tuning(&self, base: &Tuning) -> Tuning {
+        let scale = self.scale();
+
+        let (low, high) = (*base.pipe_height_range.start(), *base.pipe_height_range.end());
+        let middle = (low + high) as f32 / 2.;
+        let spread = (high - low) as f32 / 2. * scale.height_variance;
+
+        Tuning {
+            pipe_gap_x: base.pipe_gap_x * scale.pipe_gap_x,
+            pipe_gap_y: base.pipe_gap_y * scale.pipe_gap_y,
+            pipe_speed: base.pipe_speed * scale.pipe_speed,
+            gravity: base.gravity * scale.gravity,
+            pipe_height_range: (middle - spread).round() as i32..=(middle + spread).round() as i32,
+            pipe_height_jump: base.pipe_height_jump * scale.height_variance,
+            ..base.clone()
+        }
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|difficulty| difficulty.name().eq_ignore_ascii_case(text))
+            .ok_or_else(|| format!("unknown difficulty `{text}`"))
+    }
+}
+
+// Keeps the tuning the game plays with in line with the tuning file and
+// the chosen difficulty
+pub fn apply_difficulty(
+    difficulty: Res<Difficulty>,
+    base_tuning: Res<BaseTuning>,
+    mut tuning: ResMut<Tuning>,
+) {
+    let difficulty_tuning = difficulty.tuning(&base_tuning.0);
+
+    // Only writes on a change so systems watching the tuning don't run
+    // every tick.
+    if *tuning != difficulty_tuning {
+        *tuning = difficulty_tuning;
+    }
+}
+
+// Starts on the difficulty the player picked last time. A replay brings
+// its own difficulty.
+pub fn load_difficulty(
+    mut difficulty: ResMut<Difficulty>,
+    save_data: Res<SaveData>,
+    playback: Option<Res<ReplayPlayback>>,
+) {
+    if playback.is_none() {
+        *difficulty = save_data.difficulty;
+    }
+}
@@ -2,90 +2,111 @@ use bevy::prelude::*;
 
 use super::{
     AppState,
-    Collider,
     Velocity,
+    audio::PlaySound,
+    audio::SoundCue,
     game_ui::scoreboard::Scoreboard,
     bird::Bird,
-    pipes::Offset,
-    pipes::PointMarker,
-    pipes::BeenAdded,
-    pipes::StartingPosition,
+    bird::BirdCollisionEvent,
+    bird::DeathCause,
+    bird::FlapInput,
     pipes::NumberOf,
-    pipes::PIPE_X_SIZE,
-    pipes::PIPE_AMOUNT,
-    pipes::PIPE_HEIGHT_RANGE,
+    pipes::CourseRng,
+    pipes::CourseSpawner,
+    pipes::clear_course,
+    replay::RunTick,
 };
 
-use rand::prelude::*;
+/// Present while the current run can't set records or count towards the
+/// stats, like one changed from the debug console. Removed when the run
+/// is reset.
+#[derive(Resource)]
+pub struct UnrankedRun;
 
-// Restarts the game when a collision event is recieved
+/// Sent once at the end of every run, before anything is reset.
+pub struct GameOverEvent {
+    pub score: i128,
+    pub seed: u64,
+    pub cause: DeathCause,
+    /// Length of the run in fixed ticks.
+    pub ticks: u64,
+}
+
+// Ends the run when a collision event is recieved
 pub fn game_over(
-    mut bird_query: Query<(&mut Transform, &mut Velocity), (With<Bird>, Without<Collider>)>,
-    mut pipes_query: Query<(
-        &mut Transform, 
-        &mut Velocity,
-        &Offset, 
-        &mut StartingPosition, 
-        &NumberOf,
-        Option<&PointMarker>, 
-        Option<&mut BeenAdded
-    >), 
-        (With<Collider>, Without<Bird>)>,
-    mut score: ResMut<Scoreboard>,
-    collision_event: EventReader<super::bird::BirdCollisionEvent>,
+    mut collision_event: EventReader<BirdCollisionEvent>,
     mut next_state: ResMut<NextState<AppState>>,
-
-    asset_server: Res<AssetServer>,
-    audio: Res<Audio>,
+    mut game_over_event: EventWriter<GameOverEvent>,
+    score: Res<Scoreboard>,
+    course_rng: Res<CourseRng>,
+    run_tick: Res<RunTick>,
 ) {
-    let mut rand = thread_rng();
-    let mut random_heights: Vec<f32> = Vec::new();
+    if !collision_event.is_empty() {
+        // A collision can send more then one event, only one game
+        // over should happen for all of them. The first one is what
+        // killed the bird.
+        let cause = collision_event
+            .iter()
+            .next()
+            .expect("Should be Some<T>")
+            .cause;
+        collision_event.clear();
+
+        game_over_event.send(GameOverEvent {
+            score: score.score,
+            seed: course_rng.seed(),
+            cause,
+            // Counting the tick the bird died on.
+            ticks: run_tick.0 + 1,
+        });
 
-    for _ in 1..=PIPE_AMOUNT {
-        random_heights
-            .push(rand.gen_range(PIPE_HEIGHT_RANGE) as f32);
+        next_state.set(AppState::GameOver);
     }
+}
 
-    if !collision_event.is_empty() {
-        let game_over_sound = asset_server
-            .load("sounds/game_over.mp3");
-        audio.play(game_over_sound);
+// Puts the bird, pipes and score back for the next run. Runs whenever
+// a run ends, whether the bird died or the player left it.
+pub fn reset_run(
+    mut commands: Commands,
+    mut bird_query: Query<(&mut Transform, &mut Velocity), With<Bird>>,
+    pipes_query: Query<Entity, With<NumberOf>>,
+    mut score: ResMut<Scoreboard>,
+    mut course_rng: ResMut<CourseRng>,
+    mut spawner: ResMut<CourseSpawner>,
+    mut flap_input: ResMut<FlapInput>,
+) {
+    course_rng.next_run();
+    info!("Course seed: {}", course_rng.seed());
 
-        // Score
-        score.score = 0;
+    // Score
+    score.score = 0;
 
-        // Bird
-        let (mut bird_transform, mut bird_velocity) = bird_query.single_mut();
-        bird_transform.translation.y = 0.;
-        bird_transform.rotation.z = 0.;
-        bird_velocity.0 = Vec2::new(0., 0.);
+    // Bird
+    let (mut bird_transform, mut bird_velocity) = bird_query.single_mut();
+    bird_transform.translation.y = 0.;
+    bird_transform.rotation.z = 0.;
+    bird_velocity.0 = Vec2::new(0., 0.);
 
-        // Pipes
-        for (
-            mut pipe_transform, 
-            mut pipe_velocity,
-            offset, 
-            starting_position, 
-            number_of,
-            point_marker, 
-            been_added
-        ) in &mut pipes_query {
-            pipe_transform.translation.y = random_heights
-                    .get(number_of.0 - 1)
-                    .expect("Should be a valid index in random_heights") + offset.0;
+    // Flaps queued as the bird died would start the next run on their own.
+    flap_input.0 = 0;
 
-            pipe_velocity.0 = Vec2::new(0., 0.,);
+    // Removed with commands, so the systems saving this run still see it.
+    commands.remove_resource::<UnrankedRun>();
 
-            if point_marker.is_some() {
-                pipe_transform.translation.x = starting_position.0.x + PIPE_X_SIZE / 2.;
-                been_added
-                    .expect("Should be Some<T>")
-                    .0 = false;
-            } else {
-                pipe_transform.translation.x = starting_position.0.x;
-            }
-        } 
+    // Pipes, the spawner brings the new course in on the next tick.
+    clear_course(&mut commands, &pipes_query, &mut spawner);
+}
 
-        next_state.set(AppState::MainMenu);
+// Game over sound
+pub fn game_over_sound(
+    mut collision_event: EventReader<BirdCollisionEvent>,
+    mut play_sound_event: EventWriter<PlaySound>,
+) {
+    if collision_event.is_empty() {
+        return;
     }
+
+    collision_event.clear();
+
+    play_sound_event.send(PlaySound(SoundCue::GameOver));
 }
@@ -1,9 +1,18 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, window::WindowResized};
 
 pub mod fps;
+pub mod game_over;
 pub mod menu;
 pub mod scoreboard;
 pub mod high_score;
+pub mod leaderboard;
+pub mod loading;
+pub mod name_entry;
+pub mod pause;
+pub mod settings;
+pub mod stats;
+
+pub const FONT: &str = "fonts/slkscrb.ttf";
 
 pub fn setup(
     mut commands: Commands
@@ -90,3 +99,26 @@ pub struct NodeCenterSide;
 
 #[derive(Component)]
 pub struct NodeRightSide;
+
+/// Keeps a node's top margin at this fraction of the window height.
+#[derive(Component)]
+pub struct TopMargin(pub f32);
+
+// Lays out new text, and all of it again when the window is resized
+pub fn update_top_margins(
+    mut resized_events: EventReader<WindowResized>,
+    window_query: Query<&Window>,
+    mut query: Query<(&mut Style, Ref<TopMargin>)>,
+) {
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
+
+    let resized = resized_events.iter().count() > 0;
+
+    for (mut style, top_margin) in &mut query {
+        if resized || top_margin.is_added() {
+            style.margin.top = Val::Px(window.height() * top_margin.0);
+        }
+    }
+}
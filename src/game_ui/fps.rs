@@ -3,11 +3,7 @@ use bevy::{
     diagnostic::{Diagnostics, FrameTimeDiagnosticsPlugin},
 };
 
-use super::{
-    NodeLeftSide,
-    NodeCenterSide,
-    NodeRightSide,
-};
+use super::NodeLeftSide;
 
 // Constants
 const FPS_TEXT_SIZE: f32 = 48.;
@@ -20,7 +16,7 @@ pub fn setup(
     mut fps_spawned: ResMut<FpsSpawned>,
     node_query: Query<Entity, With<NodeLeftSide>>,
 ) {
-    if fps_spawned.0 == true {
+    if fps_spawned.0 {
         return;
     }
 
@@ -29,7 +25,7 @@ pub fn setup(
             TextSection::new(
                 "FPS: ",
                 TextStyle {
-                    font: asset_server.load("fonts/slkscrb.ttf"),
+                    font: asset_server.load(super::FONT),
                     font_size: FPS_TEXT_SIZE,
                     color: crate::TEXT_COLOR,
                 },
@@ -38,7 +34,7 @@ pub fn setup(
             TextSection::new(
                 "0",
                 TextStyle {
-                    font: asset_server.load("fonts/slkscrb.ttf"),
+                    font: asset_server.load(super::FONT),
                     font_size: FPS_TEXT_SIZE,
                     color: crate::TEXT_COLOR,
                 },
@@ -0,0 +1,186 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use super::{
+    NodeCenterSide,
+    TopMargin,
+
+    name_entry::PendingEntry,
+};
+
+use crate::{
+    AppState,
+    bird::DeathCause,
+    difficulty::Difficulty,
+    game_over::{GameOverEvent, UnrankedRun},
+    input::{Action, ActionInput, InputBindings},
+    replay::ReplayPlayback,
+    save::SaveData,
+    tuning::Tuning,
+};
+
+// Constants
+const GAME_OVER_TITLE_SIZE: f32 = 72.;
+const GAME_OVER_TEXT_SIZE: f32 = 36.;
+const GAME_OVER_HINT_SIZE: f32 = 28.;
+
+// How long inputs are ignored for, so a flap meant for the run that was
+// just lost doesn't skip the screen.
+const GAME_OVER_LOCKOUT: f32 = 0.75;
+
+// Initial Setup
+pub fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    query: Query<Entity, With<NodeCenterSide>>,
+    summary: Res<RunSummary>,
+) {
+    let font = asset_server.load(super::FONT);
+
+    let record = if summary.new_record { "\nNew Record!" } else { "" };
+    let seconds = summary.duration.as_secs_f32();
+
+    let rows = format!(
+        "\nScore: {}\nBest: {}{record}\nHit: {}\nTime: {}:{:04.1}",
+        summary.score,
+        summary.best,
+        summary.cause.name(),
+        (seconds / 60.) as u32,
+        seconds % 60.,
+    );
+
+    let text = commands.spawn((TextBundle::from_sections([
+        TextSection::new(
+            "Game Over\n",
+            TextStyle {
+                font: font.clone(),
+                font_size: GAME_OVER_TITLE_SIZE,
+                color: crate::TEXT_COLOR,
+            },
+        ),
+
+        TextSection::new(
+            rows,
+            TextStyle {
+                font: font.clone(),
+                font_size: GAME_OVER_TEXT_SIZE,
+                color: crate::TEXT_COLOR,
+            },
+        ),
+
+        // Filled in once the lockout is over.
+        TextSection::new(
+            "",
+            TextStyle {
+                font,
+                font_size: GAME_OVER_HINT_SIZE,
+                color: crate::TEXT_COLOR,
+            },
+        ),
+    ]),
+
+    GameOverText,
+    TopMargin(1. / 7.),
+    )).id();
+
+    let window_ui_node = query.single();
+    commands
+        .entity(window_ui_node)
+        .add_child(text);
+
+    commands.insert_resource(GameOverLockout(
+        Timer::from_seconds(GAME_OVER_LOCKOUT, TimerMode::Once)
+    ));
+}
+
+// Components, Resources, Events
+#[derive(Component)]
+pub struct GameOverText;
+
+/// How the last run went.
+#[derive(Resource, Debug, Clone)]
+pub struct RunSummary {
+    pub score: i128,
+    /// Best score of the difficulty, counting this run.
+    pub best: i128,
+    pub new_record: bool,
+    pub cause: DeathCause,
+    pub duration: Duration,
+}
+
+#[derive(Resource)]
+pub struct GameOverLockout(pub Timer);
+
+// Keeps the summary of a run that just ended. Runs in the same tick as
+// game_over, before the high score is saved.
+pub fn record_summary(
+    mut commands: Commands,
+    mut game_over_event: EventReader<GameOverEvent>,
+    save_data: Res<SaveData>,
+    difficulty: Res<Difficulty>,
+    tuning: Res<Tuning>,
+    playback: Option<Res<ReplayPlayback>>,
+    unranked: Option<Res<UnrankedRun>>,
+) {
+    for game_over in game_over_event.iter() {
+        let previous_best = save_data.high_score(*difficulty);
+
+        commands.insert_resource(RunSummary {
+            score: game_over.score,
+            best: previous_best.max(game_over.score),
+            // A replay beating the player's score isn't their record.
+            new_record: playback.is_none() && unranked.is_none() && game_over.score > previous_best,
+            cause: game_over.cause,
+            duration: Duration::from_secs_f64(game_over.ticks as f64 * tuning.time_step as f64),
+        });
+    }
+}
+
+// Leaves the screen on the confirm action once the lockout is over.
+// Runs that made it onto the leaderboard go to name entry first.
+pub fn leave_game_over(
+    time: Res<Time>,
+    action_input: Res<ActionInput>,
+    bindings: Res<InputBindings>,
+    mut lockout: ResMut<GameOverLockout>,
+    pending_entry: Res<PendingEntry>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut text_query: Query<&mut Text, With<GameOverText>>,
+) {
+    if !lockout.0.finished() {
+        lockout.0.tick(time.delta());
+
+        if lockout.0.just_finished() {
+            let mut text = text_query.single_mut();
+            text.sections[2].value = format!("\n\nPress {}", bindings.names(Action::Confirm));
+        }
+
+        return;
+    }
+
+    if action_input.just_pressed(Action::Confirm) {
+        if pending_entry.0.is_some() {
+            next_state.set(AppState::NameEntry);
+        } else {
+            next_state.set(AppState::MainMenu);
+        }
+    }
+}
+
+pub fn remove_game_over_text(
+    mut commands: Commands,
+    text_query: Query<Entity, With<GameOverText>>,
+    node_query: Query<Entity, With<NodeCenterSide>>,
+) {
+    let text = text_query.single();
+    let node = node_query.single();
+
+    commands
+        .entity(node)
+        .remove_children(&[text]);
+
+    commands
+        .entity(text)
+        .despawn();
+}
@@ -1,18 +1,18 @@
-use std::{fs::File, io::{Write, Read}};
-
-use bevy::{
-    prelude::*,
-    window::WindowCloseRequested,
-};
+use bevy::prelude::*;
 
 use super::{
-    NodeLeftSide,
-    NodeCenterSide,
     NodeRightSide,
 
     scoreboard::Scoreboard,
 };
 
+use crate::{
+    difficulty::Difficulty,
+    game_over::UnrankedRun,
+    replay::ReplayPlayback,
+    save::SaveData,
+};
+
 // Constants
 const FPS_TEXT_SIZE: f32 = 48.;
 
@@ -23,7 +23,7 @@ pub fn setup(
     mut high_score_spawned: ResMut<HighScoreSpawned>,
     window_node_query: Query<Entity, With<NodeRightSide>>,
 ) {
-    if high_score_spawned.0 == true {
+    if high_score_spawned.0 {
         return;
     }
 
@@ -32,7 +32,7 @@ pub fn setup(
             TextSection::new(
                 "High: ",
                 TextStyle {
-                    font: asset_server.load("fonts/slkscrb.ttf"),
+                    font: asset_server.load(super::FONT),
                     font_size: FPS_TEXT_SIZE,
                     color: crate::TEXT_COLOR,
                 },
@@ -41,7 +41,7 @@ pub fn setup(
             TextSection::new(
                 "0",
                 TextStyle {
-                    font: asset_server.load("fonts/slkscrb.ttf"),
+                    font: asset_server.load(super::FONT),
                     font_size: FPS_TEXT_SIZE,
                     color: crate::TEXT_COLOR,
                 },
@@ -66,9 +66,6 @@ pub struct HighScoreText;
 #[derive(Resource)]
 pub struct HighScoreSpawned(pub bool);
 
-#[derive(Resource)]
-pub struct HighScoreLoaded(pub bool);
-
 #[derive(Resource)]
 pub struct HighScore {
     pub highscore: i128,
@@ -91,42 +88,32 @@ pub fn update_highscore_text(
     text.sections[1].value = highscore.highscore.to_string();
 }
 
-// Load high score on open
+// Shows the high score of the chosen difficulty. Runs on entering the
+// menu and whenever the difficulty changes.
 pub fn load_high_score(
     mut highscore: ResMut<HighScore>,
-    mut loaded: ResMut<HighScoreLoaded>,
+    save_data: Res<SaveData>,
+    difficulty: Res<Difficulty>,
 ) {
-    if loaded.0 == true {
-        return;
-    }
-
-    if let Ok(mut file) = File::open("highscore.txt") {
-        let mut contents = String::new();
-
-        file.read_to_string(&mut contents)
-            .expect("Should be able to read contents");
-
-        highscore.highscore = contents.parse::<i128>()
-            .expect("Should be a valid i128 value");
-    }
-
-    loaded.0 = true;
+    highscore.highscore = save_data.high_score(*difficulty);
 }
 
 // Save high score on game_over
 pub fn save_high_score(
     highscore: Res<HighScore>,
+    mut save_data: ResMut<SaveData>,
+    difficulty: Res<Difficulty>,
+    playback: Option<Res<ReplayPlayback>>,
+    unranked: Option<Res<UnrankedRun>>,
 ) {
-    // Truncates file if it exists. Probably shouldn't be saving every
-    // time the game is over but will do for now.
-    let mut file = File::create("highscore.txt")
-        .expect("Should be able to create file");
-
-    let save_text = highscore
-        .highscore
-        .to_string();
-
-    file
-        .write_all(save_text.as_bytes())
-        .expect("Should be able to write to file");
+    // A replay beating the player's score isn't their record.
+    if playback.is_some() || unranked.is_some() {
+        return;
+    }
+
+    // Only touches the save when there is a new high score so it
+    // isn't rewritten after every run.
+    if highscore.highscore > save_data.high_score(*difficulty) {
+        save_data.high_scores.insert(*difficulty, highscore.highscore);
+    }
 }
@@ -0,0 +1,123 @@
+use bevy::prelude::*;
+
+use super::{NodeCenterSide, TopMargin};
+
+use crate::{
+    AppState,
+    input::{Action, ActionInput, InputBindings},
+    difficulty::Difficulty,
+    save::SaveData,
+};
+
+// Constants
+const LEADERBOARD_TITLE_SIZE: f32 = 48.;
+const LEADERBOARD_TEXT_SIZE: f32 = 28.;
+
+// Initial Setup
+pub fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    query: Query<Entity, With<NodeCenterSide>>,
+    save_data: Res<SaveData>,
+    bindings: Res<InputBindings>,
+    difficulty: Res<Difficulty>,
+) {
+    let font = asset_server.load(super::FONT);
+
+    let mut rows = String::new();
+    for (rank, entry) in save_data.leaderboard.entries(difficulty.name()).enumerate() {
+        rows.push_str(&format!(
+            "\n{:>2}. {:<12} {:>5}  {}",
+            rank + 1,
+            entry.name,
+            entry.score,
+            entry.date_text(),
+        ));
+    }
+
+    if rows.is_empty() {
+        rows.push_str("\nNo scores yet");
+    }
+
+    let text = commands.spawn((TextBundle::from_sections([
+        TextSection::new(
+            format!("Leaderboard - {}\n", *difficulty),
+            TextStyle {
+                font: font.clone(),
+                font_size: LEADERBOARD_TITLE_SIZE,
+                color: crate::TEXT_COLOR,
+            },
+        ),
+
+        TextSection::new(
+            rows,
+            TextStyle {
+                font: font.clone(),
+                font_size: LEADERBOARD_TEXT_SIZE,
+                color: crate::TEXT_COLOR,
+            },
+        ),
+
+        TextSection::new(
+            format!("\n\n{} to go back", bindings.names(Action::Back)),
+            TextStyle {
+                font,
+                font_size: LEADERBOARD_TEXT_SIZE,
+                color: crate::TEXT_COLOR,
+            },
+        ),
+    ]),
+
+    LeaderboardText,
+    TopMargin(1. / 7.),
+    )).id();
+
+    let window_ui_node = query.single();
+    commands
+        .entity(window_ui_node)
+        .add_child(text);
+}
+
+// Components, Resources, Events
+#[derive(Component)]
+pub struct LeaderboardText;
+
+// Opens the leaderboard from the main menu
+pub fn open_leaderboard(
+    action_input: Res<ActionInput>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if action_input.just_pressed(Action::Leaderboard) {
+        next_state.set(AppState::Leaderboard);
+    }
+}
+
+// Goes back to the main menu
+pub fn close_leaderboard(
+    action_input: Res<ActionInput>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if
+        action_input.just_pressed(Action::Back) ||
+        action_input.just_pressed(Action::Leaderboard)
+    {
+        next_state.set(AppState::MainMenu);
+    }
+}
+
+pub fn remove_leaderboard_text(
+    mut commands: Commands,
+    text_query: Query<Entity, With<LeaderboardText>>,
+    node_query: Query<Entity, With<NodeCenterSide>>,
+) {
+    let text = text_query.single();
+    let node = node_query.single();
+
+    commands
+        .entity(node)
+        .remove_children(&[text]);
+
+    commands
+        .entity(text)
+        .despawn();
+}
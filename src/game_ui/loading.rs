@@ -0,0 +1,134 @@
+use bevy::prelude::*;
+
+use crate::loading::LoadingProgress;
+
+// Constants
+const LOADING_TEXT_SIZE: f32 = 48.;
+const LOADING_ERROR_SIZE: f32 = 28.;
+
+const BAR_BACKGROUND_COLOR: Color = Color::rgb(0.2, 0.2, 0.2);
+const BAR_ERROR_COLOR: Color = Color::rgb(0.8, 0.15, 0.15);
+
+// Initial Setup
+pub fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+) {
+    let font = asset_server.load(super::FONT);
+
+    // Covers the whole window so nothing is seen before its sprite is
+    // loaded.
+    commands.spawn((NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        background_color: crate::BACKGROUND_COLOR.into(),
+        ..default()
+    },
+    LoadingScreen,
+    ))
+
+    .with_children(|parent| {
+        parent.spawn((TextBundle::from_sections([
+            TextSection::new(
+                "Loading",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: LOADING_TEXT_SIZE,
+                    color: crate::TEXT_COLOR,
+                },
+            ),
+
+            TextSection::new(
+                "",
+                TextStyle {
+                    font,
+                    font_size: LOADING_ERROR_SIZE,
+                    color: crate::TEXT_COLOR,
+                },
+            ),
+        ]),
+
+        LoadingText,
+        ));
+
+        parent.spawn(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(50.), Val::Px(24.)),
+                margin: UiRect::top(Val::Px(24.)),
+                ..default()
+            },
+            background_color: BAR_BACKGROUND_COLOR.into(),
+            ..default()
+        })
+
+        .with_children(|parent| {
+            parent.spawn((NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(0.), Val::Percent(100.)),
+                    ..default()
+                },
+                background_color: crate::TEXT_COLOR.into(),
+                ..default()
+            },
+            LoadingBar,
+            ));
+        });
+    });
+}
+
+// Components, Resources, Events
+#[derive(Component)]
+pub struct LoadingScreen;
+
+#[derive(Component)]
+pub struct LoadingText;
+
+#[derive(Component)]
+pub struct LoadingBar;
+
+// Fills the bar as assets load, or shows what went wrong
+pub fn update_loading_screen(
+    progress: Res<LoadingProgress>,
+    mut bar_query: Query<(&mut Style, &mut BackgroundColor), With<LoadingBar>>,
+    mut text_query: Query<&mut Text, With<LoadingText>>,
+    mut window_query: Query<&mut Window>,
+) {
+    if !progress.is_changed() {
+        return;
+    }
+
+    let (mut style, mut background_color) = bar_query.single_mut();
+    style.size.width = Val::Percent(progress.fraction() * 100.);
+
+    if let Some(path) = progress.failed {
+        *background_color = BAR_ERROR_COLOR.into();
+
+        let mut text = text_query.single_mut();
+        text.sections[0].value = "Loading failed".to_string();
+        text.sections[1].value = format!(
+            "\n\nCouldn't load {path}\nCheck that the assets folder is next to the game",
+        );
+
+        // The font may be what failed, the title is always readable.
+        if let Ok(mut window) = window_query.get_single_mut() {
+            window.title = format!("Flappy Qube - couldn't load {path}");
+        }
+    }
+}
+
+pub fn remove_loading_screen(
+    mut commands: Commands,
+    screen_query: Query<Entity, With<LoadingScreen>>,
+) {
+    let screen = screen_query.single();
+
+    commands
+        .entity(screen)
+        .despawn_recursive();
+}
@@ -1,40 +1,63 @@
 use bevy::prelude::*;
 
-use super::{
-    NodeLeftSide,
-    NodeCenterSide,
-    NodeRightSide,
+use super::{NodeCenterSide, TopMargin};
+
+use crate::{
+    difficulty::Difficulty,
+    input::{Action, ActionInput, InputBindings},
+    save::SaveData,
 };
 
 // Constants
 const MENU_TEXT_SIZE: f32 = 48.;
+const MENU_HINT_SIZE: f32 = 28.;
 
 // Initial Setup
 pub fn setup(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     query: Query<Entity, With<NodeCenterSide>>,
-    window_query: Query<&Window>,
+    difficulty: Res<Difficulty>,
+    bindings: Res<InputBindings>,
 ) {
-    let window = window_query.single();
-    let text_height = window.height() / 5.;
+    let text = commands.spawn((TextBundle::from_sections([
+        TextSection::new(
+            format!("Press {}!", bindings.names(Action::Flap)),
+            TextStyle {
+                font: asset_server.load(super::FONT),
+                font_size: MENU_TEXT_SIZE,
+                color: crate::TEXT_COLOR,
+            },
+        ),
 
-    let text = commands.spawn((TextBundle::from_section(
-            "Press Space or M1!",
+        TextSection::new(
+            difficulty_text(*difficulty),
             TextStyle {
-                font: asset_server.load("fonts/slkscrb.ttf"),
+                font: asset_server.load(super::FONT),
                 font_size: MENU_TEXT_SIZE,
                 color: crate::TEXT_COLOR,
-        },
-    ).with_style(Style {
-        margin: UiRect {
-            top: Val::Px(text_height),
-            ..default()
-        },
-        ..default()
-    }),
+            },
+        ),
+
+        TextSection::new(
+            format!(
+                "\n\n{}/{} - Difficulty\n{} - Leaderboard\n{} - Stats\n{} - Settings",
+                bindings.names(Action::Left),
+                bindings.names(Action::Right),
+                bindings.names(Action::Leaderboard),
+                bindings.names(Action::Stats),
+                bindings.names(Action::Settings),
+            ),
+            TextStyle {
+                font: asset_server.load(super::FONT),
+                font_size: MENU_HINT_SIZE,
+                color: crate::TEXT_COLOR,
+            },
+        ),
+    ]),
 
     MenuText,
+    TopMargin(1. / 5.),
     )).id();
 
     let window_ui_node = query.single();
@@ -46,6 +69,32 @@ pub fn setup(
 #[derive(Component)]
 pub struct MenuText;
 
+fn difficulty_text(difficulty: Difficulty) -> String {
+    format!("\n\n< {difficulty} >")
+}
+
+// Picks the difficulty with Left and Right
+pub fn change_difficulty(
+    action_input: Res<ActionInput>,
+    mut difficulty: ResMut<Difficulty>,
+    mut save_data: ResMut<SaveData>,
+    mut text_query: Query<&mut Text, With<MenuText>>,
+) {
+    let changed = if action_input.just_pressed(Action::Left) {
+        difficulty.previous()
+    } else if action_input.just_pressed(Action::Right) {
+        difficulty.next()
+    } else {
+        return;
+    };
+
+    *difficulty = changed;
+    save_data.difficulty = changed;
+
+    let mut text = text_query.single_mut();
+    text.sections[1].value = difficulty_text(changed);
+}
+
 pub fn remove_menu_text(
     mut commands: Commands,
     text_query: Query<Entity, With<MenuText>>,
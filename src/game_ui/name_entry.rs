@@ -0,0 +1,194 @@
+use bevy::prelude::*;
+
+use super::{NodeCenterSide, TopMargin};
+
+use crate::{
+    AppState,
+    difficulty::Difficulty,
+    game_over::{GameOverEvent, UnrankedRun},
+    input::{Action, ActionInput, Binding, InputBindings},
+    leaderboard::{
+        LeaderboardEntry,
+        NAME_MAX_LENGTH,
+        UNKNOWN_NAME,
+    },
+    replay::ReplayPlayback,
+    save::SaveData,
+};
+
+// Constants
+const NAME_ENTRY_TEXT_SIZE: f32 = 48.;
+
+// Initial Setup
+pub fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    query: Query<Entity, With<NodeCenterSide>>,
+    pending_entry: Res<PendingEntry>,
+    bindings: Res<InputBindings>,
+) {
+    let text_style = TextStyle {
+        font: asset_server.load(super::FONT),
+        font_size: NAME_ENTRY_TEXT_SIZE,
+        color: crate::TEXT_COLOR,
+    };
+
+    let score = pending_entry.0
+        .as_ref()
+        .map_or(0, |entry| entry.score);
+
+    let text = commands.spawn((TextBundle::from_sections([
+        TextSection::new(
+            format!("New Record! {score}\nName: "),
+            text_style.clone(),
+        ),
+
+        TextSection::new(
+            "_",
+            text_style.clone(),
+        ),
+
+        TextSection::new(
+            format!("\n{} to save", save_hint(&bindings)),
+            text_style,
+        ),
+    ]),
+
+    NameEntryText,
+    TopMargin(1. / 5.),
+    )).id();
+
+    let window_ui_node = query.single();
+    commands
+        .entity(window_ui_node)
+        .add_child(text);
+}
+
+// Components, Resources, Events
+#[derive(Component)]
+pub struct NameEntryText;
+
+/// A finished run waiting for the player to type their name.
+#[derive(Resource, Default)]
+pub struct PendingEntry(pub Option<LeaderboardEntry>);
+
+// Keeps runs that made it onto the leaderboard so the game over screen
+// sends the player to name entry instead of the menu.
+pub fn check_new_entry(
+    mut game_over_event: EventReader<GameOverEvent>,
+    mut pending_entry: ResMut<PendingEntry>,
+    save_data: Res<SaveData>,
+    playback: Option<Res<ReplayPlayback>>,
+    unranked: Option<Res<UnrankedRun>>,
+    difficulty: Res<Difficulty>,
+) {
+    // Replays are someone else's run, they don't go on the leaderboard.
+    if playback.is_some() || unranked.is_some() {
+        return;
+    }
+
+    for game_over in game_over_event.iter() {
+        if !save_data.leaderboard.qualifies(game_over.score, difficulty.name()) {
+            continue;
+        }
+
+        pending_entry.0 = Some(LeaderboardEntry::new(
+            String::new(),
+            game_over.score,
+            game_over.seed,
+            difficulty.name(),
+        ));
+    }
+}
+
+// Keys type the name, so only Return and the other kinds of Confirm
+// bindings save it.
+fn save_hint(bindings: &InputBindings) -> String {
+    let mut names = vec![Binding::Key(KeyCode::Return).name()];
+    names.extend(bindings
+        .get(Action::Confirm)
+        .iter()
+        .filter(|binding| !matches!(binding, Binding::Key(_)))
+        .map(Binding::name)
+    );
+
+    names.join(" or ")
+}
+
+// Drops characters typed before the name entry opened, like the Space
+// that left the game over screen
+pub fn clear_typed_characters(
+    mut received_characters: ResMut<Events<ReceivedCharacter>>,
+) {
+    received_characters.clear();
+}
+
+// Typing, backspace and enter while entering a name. Players without a
+// keyboard save with Confirm and get the default name.
+pub fn type_name(
+    mut received_characters: EventReader<ReceivedCharacter>,
+    keyboard_input: Res<Input<KeyCode>>,
+    action_input: Res<ActionInput>,
+    bindings: Res<InputBindings>,
+    mut pending_entry: ResMut<PendingEntry>,
+    mut save_data: ResMut<SaveData>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut text_query: Query<&mut Text, With<NameEntryText>>,
+) {
+    let Some(entry) = pending_entry.0.as_mut() else {
+        return;
+    };
+
+    for received in received_characters.iter() {
+        if !received.char.is_control() && entry.name.chars().count() < NAME_MAX_LENGTH {
+            entry.name.push(received.char);
+        }
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Back) {
+        entry.name.pop();
+    }
+
+    // Confirm bound to a key like Space would save halfway through a name.
+    let confirmed_by_key = bindings
+        .get(Action::Confirm)
+        .iter()
+        .any(|binding| matches!(binding, Binding::Key(key) if keyboard_input.just_pressed(*key)));
+
+    if keyboard_input.just_pressed(KeyCode::Return) ||
+        (action_input.just_pressed(Action::Confirm) && !confirmed_by_key)
+    {
+        let mut entry = pending_entry.0
+            .take()
+            .expect("Should be Some<T>");
+
+        entry.name = entry.name.trim().to_string();
+        if entry.name.is_empty() {
+            entry.name = UNKNOWN_NAME.to_string();
+        }
+
+        save_data.leaderboard.insert(entry);
+        next_state.set(AppState::MainMenu);
+        return;
+    }
+
+    let mut text = text_query.single_mut();
+    text.sections[1].value = format!("{}_", entry.name);
+}
+
+pub fn remove_name_entry_text(
+    mut commands: Commands,
+    text_query: Query<Entity, With<NameEntryText>>,
+    node_query: Query<Entity, With<NodeCenterSide>>,
+) {
+    let text = text_query.single();
+    let node = node_query.single();
+
+    commands
+        .entity(node)
+        .remove_children(&[text]);
+
+    commands
+        .entity(text)
+        .despawn();
+}
@@ -0,0 +1,285 @@
+use bevy::{
+    input::gamepad::GamepadConnectionEvent,
+    prelude::*,
+};
+
+use crate::{
+    AppState,
+    PauseState,
+    input::{Action, ActionInput, InputBindings},
+};
+
+// Constants
+const PAUSE_TITLE_SIZE: f32 = 72.;
+const PAUSE_TEXT_SIZE: f32 = 48.;
+const PAUSE_HINT_SIZE: f32 = 28.;
+
+const OVERLAY_COLOR: Color = Color::rgba(0., 0., 0., 0.5);
+
+const BUTTON_COLOR: Color = Color::rgba(0., 0., 0., 0.4);
+const SELECTED_BUTTON_COLOR: Color = Color::rgba(1., 1., 1., 0.25);
+
+// Big enough to hit with a thumb.
+const PAUSE_BUTTON_SIZE: f32 = 72.;
+
+// Initial Setup
+pub fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut selection: ResMut<PauseSelection>,
+    bindings: Res<InputBindings>,
+) {
+    *selection = PauseSelection::default();
+
+    let font = asset_server.load(super::FONT);
+
+    // Covers the whole window so the frozen game is dimmed behind the menu.
+    commands.spawn((NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        background_color: OVERLAY_COLOR.into(),
+        ..default()
+    },
+    PauseMenu,
+    ))
+
+    .with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+            "Paused",
+            TextStyle {
+                font: font.clone(),
+                font_size: PAUSE_TITLE_SIZE,
+                color: crate::TEXT_COLOR,
+            },
+        ));
+
+        // One button per option so the menu can be tapped or clicked as
+        // well as picked with the keys.
+        for option in PauseOption::ALL {
+            parent.spawn((ButtonBundle {
+                style: Style {
+                    margin: UiRect::all(Val::Px(8.)),
+                    padding: UiRect::all(Val::Px(12.)),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                background_color: button_color(option, *selection).into(),
+                ..default()
+            },
+            option,
+            ))
+
+            .with_children(|parent| {
+                parent.spawn(TextBundle::from_section(
+                    option.name(),
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: PAUSE_TEXT_SIZE,
+                        color: crate::TEXT_COLOR,
+                    },
+                ));
+            });
+        }
+
+        parent.spawn(TextBundle::from_section(
+            format!(
+                "\nUp/Down and {}\n{} to resume",
+                bindings.names(Action::Confirm),
+                bindings.names(Action::Pause),
+            ),
+            TextStyle {
+                font,
+                font_size: PAUSE_HINT_SIZE,
+                color: crate::TEXT_COLOR,
+            },
+        ));
+    });
+}
+
+// An on screen pause button for touch screens, in the top right corner
+// where the bird never is
+pub fn setup_pause_button(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+) {
+    commands.spawn((ButtonBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Px(16.),
+                right: Val::Px(16.),
+                ..default()
+            },
+            size: Size::new(Val::Px(PAUSE_BUTTON_SIZE), Val::Px(PAUSE_BUTTON_SIZE)),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        background_color: BUTTON_COLOR.into(),
+        ..default()
+    },
+    PauseButton,
+    ))
+
+    .with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+            "II",
+            TextStyle {
+                font: asset_server.load(super::FONT),
+                font_size: PAUSE_TEXT_SIZE,
+                color: crate::TEXT_COLOR,
+            },
+        ));
+    });
+}
+
+// Components, Resources, Events
+#[derive(Component)]
+pub struct PauseMenu;
+
+#[derive(Component)]
+pub struct PauseButton;
+
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PauseOption {
+    #[default]
+    Resume,
+    Restart,
+    QuitToMenu,
+}
+
+impl PauseOption {
+    const ALL: [PauseOption; 3] = [
+        PauseOption::Resume,
+        PauseOption::Restart,
+        PauseOption::QuitToMenu,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            PauseOption::Resume => "Resume",
+            PauseOption::Restart => "Restart",
+            PauseOption::QuitToMenu => "Quit to Menu",
+        }
+    }
+}
+
+/// The highlighted option of the pause menu.
+#[derive(Resource, Default, Clone, Copy)]
+pub struct PauseSelection(pub PauseOption);
+
+fn button_color(option: PauseOption, selection: PauseSelection) -> Color {
+    if option == selection.0 {
+        SELECTED_BUTTON_COLOR
+    } else {
+        BUTTON_COLOR
+    }
+}
+
+pub fn pause_game(
+    action_input: Res<ActionInput>,
+    button_query: Query<&Interaction, (Changed<Interaction>, With<PauseButton>)>,
+    mut next_pause_state: ResMut<NextState<PauseState>>,
+) {
+    if
+        action_input.just_pressed(Action::Pause) ||
+        button_query.iter().any(|interaction| *interaction == Interaction::Clicked)
+    {
+        next_pause_state.set(PauseState::Paused);
+    }
+}
+
+// Pauses a run when a gamepad is unplugged so the bird doesn't fall while
+// the player reconnects it
+pub fn pause_on_disconnect(
+    mut connection_events: EventReader<GamepadConnectionEvent>,
+    mut next_pause_state: ResMut<NextState<PauseState>>,
+) {
+    if connection_events.iter().any(|event| event.disconnected()) {
+        next_pause_state.set(PauseState::Paused);
+    }
+}
+
+// Moves through the pause menu and picks an option
+pub fn pause_menu(
+    action_input: Res<ActionInput>,
+    mut selection: ResMut<PauseSelection>,
+    mut next_pause_state: ResMut<NextState<PauseState>>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut button_query: Query<(&PauseOption, &Interaction, &mut BackgroundColor)>,
+) {
+    if
+        action_input.just_pressed(Action::Pause) ||
+        action_input.just_pressed(Action::Back)
+    {
+        next_pause_state.set(PauseState::Running);
+        return;
+    }
+
+    let index = PauseOption::ALL
+        .iter()
+        .position(|option| *option == selection.0)
+        .expect("Should be in PauseOption::ALL");
+
+    if action_input.just_pressed(Action::Up) && index > 0 {
+        selection.0 = PauseOption::ALL[index - 1];
+    }
+
+    if action_input.just_pressed(Action::Down) && index + 1 < PauseOption::ALL.len() {
+        selection.0 = PauseOption::ALL[index + 1];
+    }
+
+    let clicked = button_query
+        .iter()
+        .find(|(_, interaction, _)| **interaction == Interaction::Clicked)
+        .map(|(option, _, _)| *option);
+
+    if let Some(option) = clicked {
+        selection.0 = option;
+    }
+
+    if selection.is_changed() {
+        for (option, _, mut background_color) in &mut button_query {
+            *background_color = button_color(*option, *selection).into();
+        }
+    }
+
+    if clicked.is_some() || action_input.just_pressed(Action::Confirm) {
+        next_pause_state.set(PauseState::Running);
+
+        match selection.0 {
+            PauseOption::Resume => (),
+            // Leaving the run and entering it again resets it.
+            PauseOption::Restart => next_state.set(AppState::InGame),
+            PauseOption::QuitToMenu => next_state.set(AppState::MainMenu),
+        }
+    }
+}
+
+pub fn remove_pause_menu(
+    mut commands: Commands,
+    menu_query: Query<Entity, With<PauseMenu>>,
+) {
+    let menu = menu_query.single();
+
+    commands
+        .entity(menu)
+        .despawn_recursive();
+}
+
+pub fn remove_pause_button(
+    mut commands: Commands,
+    button_query: Query<Entity, With<PauseButton>>,
+) {
+    let button = button_query.single();
+
+    commands
+        .entity(button)
+        .despawn_recursive();
+}
@@ -1,10 +1,6 @@
 use bevy::prelude::*;
 
-use super::{
-    NodeLeftSide,
-    NodeCenterSide,
-    NodeRightSide,
-};
+use super::{NodeCenterSide, TopMargin};
 
 // Constants
 const SCOREBOARD_TEXT_SIZE: f32 = 72.;
@@ -14,36 +10,27 @@ pub fn setup(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     query: Query<Entity, With<NodeCenterSide>>,
-    window_query: Query<&Window>,
 ) {
-    let window = window_query.single();
-    let text_height = window.height() / 7.;
-
     let text = commands.spawn((TextBundle::from_sections([
         TextSection::new(
             "Score: ",
             TextStyle {
-                font: asset_server.load("fonts/slkscrb.ttf"),
+                font: asset_server.load(super::FONT),
                 font_size: SCOREBOARD_TEXT_SIZE,
                 color: crate::TEXT_COLOR,
         }),
         
         TextSection::from_style(
             TextStyle {
-                font: asset_server.load("fonts/slkscrb.ttf"),
+                font: asset_server.load(super::FONT),
                 font_size: SCOREBOARD_TEXT_SIZE,
                 color: crate::TEXT_COLOR,
             }
         )
-    ]).with_style(Style {
-        margin: UiRect {
-            top: Val::Px(text_height),
-            ..default()
-        },
-        ..default()
-    }),
+    ]),
 
     ScoreboardText,
+    TopMargin(1. / 7.),
     )).id();
 
     let window_ui_node = query.single();
@@ -0,0 +1,308 @@
+use bevy::prelude::*;
+
+use super::{NodeCenterSide, TopMargin};
+
+use crate::{
+    AppState,
+    audio::{AudioSettings, VolumeChannel},
+    input::{Action, ActionInput, Binding, InputBindings},
+    save::SaveData,
+};
+
+// Constants
+const SETTINGS_TITLE_SIZE: f32 = 48.;
+const SETTINGS_TEXT_SIZE: f32 = 28.;
+
+// Initial Setup
+pub fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    query: Query<Entity, With<NodeCenterSide>>,
+    bindings: Res<InputBindings>,
+    audio_settings: Res<AudioSettings>,
+    mut settings_menu: ResMut<SettingsMenu>,
+) {
+    *settings_menu = SettingsMenu::default();
+
+    let font = asset_server.load(super::FONT);
+
+    let text = commands.spawn((TextBundle::from_sections([
+        TextSection::new(
+            "Settings\n",
+            TextStyle {
+                font: font.clone(),
+                font_size: SETTINGS_TITLE_SIZE,
+                color: crate::TEXT_COLOR,
+            },
+        ),
+
+        TextSection::new(
+            rows_text(&bindings, &audio_settings, &settings_menu),
+            TextStyle {
+                font: font.clone(),
+                font_size: SETTINGS_TEXT_SIZE,
+                color: crate::TEXT_COLOR,
+            },
+        ),
+
+        TextSection::new(
+            hint(&bindings, &settings_menu),
+            TextStyle {
+                font,
+                font_size: SETTINGS_TEXT_SIZE,
+                color: crate::TEXT_COLOR,
+            },
+        ),
+    ]),
+
+    SettingsText,
+    TopMargin(1. / 7.),
+    )).id();
+
+    let window_ui_node = query.single();
+    commands
+        .entity(window_ui_node)
+        .add_child(text);
+}
+
+// Components, Resources, Events
+#[derive(Component)]
+pub struct SettingsText;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SettingsRow {
+    Binding(Action),
+    Volume(VolumeChannel),
+    Mute,
+}
+
+const ROWS: [SettingsRow; 16] = [
+    SettingsRow::Binding(Action::Flap),
+    SettingsRow::Binding(Action::Pause),
+    SettingsRow::Binding(Action::Confirm),
+    SettingsRow::Binding(Action::Back),
+    SettingsRow::Binding(Action::Leaderboard),
+    SettingsRow::Binding(Action::Stats),
+    SettingsRow::Binding(Action::Settings),
+    SettingsRow::Binding(Action::ResetControls),
+    SettingsRow::Binding(Action::Up),
+    SettingsRow::Binding(Action::Down),
+    SettingsRow::Binding(Action::Left),
+    SettingsRow::Binding(Action::Right),
+    SettingsRow::Volume(VolumeChannel::Master),
+    SettingsRow::Volume(VolumeChannel::Sfx),
+    SettingsRow::Volume(VolumeChannel::Music),
+    SettingsRow::Mute,
+];
+
+/// The row picked on the settings screen and whether the next key or
+/// button press rebinds it.
+#[derive(Resource, Default)]
+pub struct SettingsMenu {
+    selected: usize,
+    listening: bool,
+    // Why the last rebind was refused.
+    refused: Option<String>,
+}
+
+impl SettingsMenu {
+    fn row(&self) -> SettingsRow {
+        ROWS[self.selected]
+    }
+}
+
+fn rows_text(
+    bindings: &InputBindings,
+    audio_settings: &AudioSettings,
+    settings_menu: &SettingsMenu,
+) -> String {
+    ROWS
+        .iter()
+        .enumerate()
+        .map(|(index, row)| {
+            let selected = index == settings_menu.selected;
+            let marker = if selected { "> " } else { "  " };
+
+            let row_text = match row {
+                SettingsRow::Binding(action) if selected && settings_menu.listening => {
+                    format!("{}: ...", action.name())
+                }
+                SettingsRow::Binding(action) => {
+                    format!("{}: {}", action.name(), bindings.names(*action))
+                }
+                SettingsRow::Volume(channel) => format!(
+                    "{}: {}%",
+                    channel.name(),
+                    (audio_settings.volume(*channel) * 100.).round(),
+                ),
+                SettingsRow::Mute => format!(
+                    "Sound: {}",
+                    if audio_settings.muted { "Muted" } else { "On" },
+                ),
+            };
+
+            // A gap between the controls and the audio settings.
+            let gap = if *row == SettingsRow::Volume(VolumeChannel::Master) { "\n" } else { "" };
+
+            format!("\n{gap}{marker}{row_text}")
+        })
+        .collect()
+}
+
+fn hint(bindings: &InputBindings, settings_menu: &SettingsMenu) -> String {
+    if settings_menu.listening {
+        return "\n\nPress a key or button to add or remove it\nEsc - Cancel".to_string();
+    }
+
+    let change = match settings_menu.row() {
+        SettingsRow::Binding(_) => format!("{} - Rebind", bindings.names(Action::Confirm)),
+        SettingsRow::Volume(_) => format!(
+            "{}/{} - Volume",
+            bindings.names(Action::Left),
+            bindings.names(Action::Right),
+        ),
+        SettingsRow::Mute => format!("{} - Mute", bindings.names(Action::Confirm)),
+    };
+
+    let refused = settings_menu.refused
+        .as_ref()
+        .map_or(String::new(), |refused| format!("\n\n{refused}"));
+
+    format!(
+        "{refused}\n\n{}/{} and {change}\n{} - Reset controls\n{} to go back",
+        bindings.names(Action::Up),
+        bindings.names(Action::Down),
+        bindings.names(Action::ResetControls),
+        bindings.names(Action::Back),
+    )
+}
+
+// Opens the settings screen from the main menu
+pub fn open_settings(
+    action_input: Res<ActionInput>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if action_input.just_pressed(Action::Settings) {
+        next_state.set(AppState::Settings);
+    }
+}
+
+// Picks a row and changes it. Actions are rebound with the next key or
+// button pressed, Escape always cancels listening so it can't be bound
+// here. A binding that already does something else on the same screen
+// is refused.
+pub fn settings_menu(
+    keyboard_input: Res<Input<KeyCode>>,
+    mouse_input: Res<Input<MouseButton>>,
+    gamepad_input: Res<Input<GamepadButton>>,
+    touches: Res<Touches>,
+    action_input: Res<ActionInput>,
+    mut settings_menu: ResMut<SettingsMenu>,
+    mut bindings: ResMut<InputBindings>,
+    mut audio_settings: ResMut<AudioSettings>,
+    mut save_data: ResMut<SaveData>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut text_query: Query<&mut Text, With<SettingsText>>,
+) {
+    // Shown until the screen changes again.
+    settings_menu.refused = None;
+
+    if settings_menu.listening {
+        let pressed = keyboard_input
+            .get_just_pressed()
+            .next()
+            .map(|key| Binding::Key(*key))
+            .or_else(|| mouse_input
+                .get_just_pressed()
+                .next()
+                .map(|button| Binding::Mouse(*button))
+            )
+            .or_else(|| gamepad_input
+                .get_just_pressed()
+                .next()
+                .map(|button| Binding::Gamepad(button.button_type))
+            )
+            .or_else(|| touches.any_just_pressed().then_some(Binding::Touch));
+
+        match pressed {
+            Some(Binding::Key(KeyCode::Escape)) => (),
+            Some(binding) => {
+                if let SettingsRow::Binding(action) = settings_menu.row() {
+                    if let Err(other) = bindings.toggle(action, binding) {
+                        settings_menu.refused = Some(format!(
+                            "{} is already {}",
+                            binding.name(),
+                            other.name(),
+                        ));
+                    }
+                }
+            }
+            None => return,
+        }
+
+        settings_menu.listening = false;
+    } else if action_input.just_pressed(Action::Back) {
+        next_state.set(AppState::MainMenu);
+        return;
+    } else if action_input.just_pressed(Action::Up) {
+        settings_menu.selected = (settings_menu.selected + ROWS.len() - 1) % ROWS.len();
+    } else if action_input.just_pressed(Action::Down) {
+        settings_menu.selected = (settings_menu.selected + 1) % ROWS.len();
+    } else if let Some(steps) = volume_steps(&action_input) {
+        match settings_menu.row() {
+            SettingsRow::Volume(channel) => audio_settings.step_volume(channel, steps),
+            SettingsRow::Mute => audio_settings.muted = !audio_settings.muted,
+            SettingsRow::Binding(_) => return,
+        }
+    } else if action_input.just_pressed(Action::Confirm) {
+        match settings_menu.row() {
+            SettingsRow::Binding(_) => settings_menu.listening = true,
+            SettingsRow::Mute => audio_settings.muted = !audio_settings.muted,
+            SettingsRow::Volume(_) => return,
+        }
+    } else if action_input.just_pressed(Action::ResetControls) {
+        *bindings = InputBindings::default();
+    } else {
+        return;
+    }
+
+    if save_data.bindings != *bindings {
+        save_data.bindings = bindings.clone();
+    }
+
+    if save_data.audio != *audio_settings {
+        save_data.audio = audio_settings.clone();
+    }
+
+    let mut text = text_query.single_mut();
+    text.sections[1].value = rows_text(&bindings, &audio_settings, &settings_menu);
+    text.sections[2].value = hint(&bindings, &settings_menu);
+}
+
+// Left and Right as volume steps
+fn volume_steps(action_input: &ActionInput) -> Option<i32> {
+    if action_input.just_pressed(Action::Left) {
+        Some(-1)
+    } else if action_input.just_pressed(Action::Right) {
+        Some(1)
+    } else {
+        None
+    }
+}
+
+pub fn remove_settings_text(
+    mut commands: Commands,
+    text_query: Query<Entity, With<SettingsText>>,
+    node_query: Query<Entity, With<NodeCenterSide>>,
+) {
+    let text = text_query.single();
+    let node = node_query.single();
+
+    commands
+        .entity(node)
+        .remove_children(&[text]);
+
+    commands
+        .entity(text)
+        .despawn();
+}
@@ -0,0 +1,131 @@
+use bevy::prelude::*;
+
+use super::{NodeCenterSide, TopMargin};
+
+use crate::{
+    AppState,
+    input::{Action, ActionInput, InputBindings},
+    save::SaveData,
+};
+
+// Constants
+const STATS_TITLE_SIZE: f32 = 48.;
+const STATS_TEXT_SIZE: f32 = 28.;
+
+// Initial Setup
+pub fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    query: Query<Entity, With<NodeCenterSide>>,
+    save_data: Res<SaveData>,
+    bindings: Res<InputBindings>,
+) {
+    let font = asset_server.load(super::FONT);
+
+    let stats = &save_data.stats;
+    let seconds = stats.time_in_game().as_secs();
+
+    let rows = format!(
+        "\nGames played: {}\
+         \nAverage score: {:.1}\
+         \nTotal points: {}\
+         \nJumps: {}\
+         \nTime in game: {}h {}m {}s\
+         \n\nDeaths\
+         \n  Pipes: {}\
+         \n  Ceiling: {}\
+         \n  Floor: {}",
+        stats.games_played,
+        stats.average_score(),
+        stats.points,
+        stats.jumps,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+        stats.deaths.pipe,
+        stats.deaths.ceiling,
+        stats.deaths.floor,
+    );
+
+    let text = commands.spawn((TextBundle::from_sections([
+        TextSection::new(
+            "Stats\n",
+            TextStyle {
+                font: font.clone(),
+                font_size: STATS_TITLE_SIZE,
+                color: crate::TEXT_COLOR,
+            },
+        ),
+
+        TextSection::new(
+            rows,
+            TextStyle {
+                font: font.clone(),
+                font_size: STATS_TEXT_SIZE,
+                color: crate::TEXT_COLOR,
+            },
+        ),
+
+        TextSection::new(
+            format!("\n\n{} to go back", bindings.names(Action::Back)),
+            TextStyle {
+                font,
+                font_size: STATS_TEXT_SIZE,
+                color: crate::TEXT_COLOR,
+            },
+        ),
+    ]),
+
+    StatsText,
+    TopMargin(1. / 7.),
+    )).id();
+
+    let window_ui_node = query.single();
+    commands
+        .entity(window_ui_node)
+        .add_child(text);
+}
+
+// Components, Resources, Events
+#[derive(Component)]
+pub struct StatsText;
+
+// Opens the stats screen from the main menu
+pub fn open_stats(
+    action_input: Res<ActionInput>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if action_input.just_pressed(Action::Stats) {
+        next_state.set(AppState::Stats);
+    }
+}
+
+// Goes back to the main menu
+pub fn close_stats(
+    action_input: Res<ActionInput>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if
+        action_input.just_pressed(Action::Back) ||
+        action_input.just_pressed(Action::Stats)
+    {
+        next_state.set(AppState::MainMenu);
+    }
+}
+
+pub fn remove_stats_text(
+    mut commands: Commands,
+    text_query: Query<Entity, With<StatsText>>,
+    node_query: Query<Entity, With<NodeCenterSide>>,
+) {
+    let text = text_query.single();
+    let node = node_query.single();
+
+    commands
+        .entity(node)
+        .remove_children(&[text]);
+
+    commands
+        .entity(text)
+        .despawn();
+}
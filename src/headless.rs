@@ -0,0 +1,235 @@
+//! Runs the game without a window, renderer or audio.
+//!
+//! [`Simulation`] steps the game one fixed tick at a time which is what
+//! tests and bots want. [`run`] and [`run_replay`] are the `--headless`
+//! modes of the binary.
+
+use bevy::{
+    prelude::*,
+    time::TimeUpdateStrategy,
+};
+
+use super::{
+    AppState,
+    Collider,
+    SimulationPlugin,
+    Velocity,
+    bird::Bird,
+    bird::FlapInput,
+    game_ui::scoreboard::Scoreboard,
+    pipes::CourseRng,
+    pipes::PointMarker,
+    replay::Replay,
+    replay::ReplayRecorder,
+};
+
+// How far under the middle of a gap the autopilot lets the bird fall
+// before flapping.
+const AUTOPILOT_MARGIN: f32 = 40.;
+
+/// The game simulation, advanced by hand instead of by the clock.
+pub struct Simulation {
+    app: App,
+}
+
+impl Simulation {
+    /// A simulation playing a random course every run.
+    pub fn new() -> Self {
+        Self::from_plugin(SimulationPlugin::default())
+    }
+
+    /// A simulation playing the course of `seed` on every run.
+    pub fn with_seed(seed: u64) -> Self {
+        Self::from_plugin(SimulationPlugin {
+            seed: Some(seed),
+            ..default()
+        })
+    }
+
+    /// A simulation that starts by playing `replay` back.
+    pub fn with_replay(replay: Replay) -> Self {
+        Self::from_plugin(SimulationPlugin {
+            replay: Some(replay),
+            ..default()
+        })
+    }
+
+    /// A simulation set up by `plugin`.
+    pub fn from_plugin(plugin: SimulationPlugin) -> Self {
+        let mut app = App::new();
+
+        app
+            .add_plugins(MinimalPlugins)
+            .add_plugin(plugin);
+
+        // Time is frozen at startup so that the only time the fixed
+        // schedule ever sees is the tick added in step.
+        let startup = app.world.resource::<Time>().startup();
+        app.insert_resource(TimeUpdateStrategy::ManualInstant(startup));
+
+        // Runs the startup systems without running a tick.
+        app.setup();
+        app.update();
+
+        Simulation { app }
+    }
+
+    /// Advances the game by exactly one fixed tick, flapping first if
+    /// `flap` is set.
+    pub fn step(&mut self, flap: bool) {
+        if flap {
+            self.app.world.resource_mut::<FlapInput>().0 += 1;
+        }
+
+        let mut fixed_time = self.app.world.resource_mut::<FixedTime>();
+        let period = fixed_time.period;
+        fixed_time.tick(period);
+
+        self.app.update();
+    }
+
+    pub fn score(&self) -> i128 {
+        self.app.world.resource::<Scoreboard>().score
+    }
+
+    /// Seed of the course being played.
+    pub fn seed(&self) -> u64 {
+        self.app.world.resource::<CourseRng>().seed()
+    }
+
+    /// Replay of the current run, or of the last one when not in a run.
+    pub fn replay(&self) -> &Replay {
+        &self.app.world.resource::<ReplayRecorder>().replay
+    }
+
+    /// Goes back to the menu from the game over screen, which needs the
+    /// player's input otherwise.
+    pub fn leave_game_over(&mut self) {
+        if self.state() == AppState::GameOver {
+            self.app.world
+                .resource_mut::<NextState<AppState>>()
+                .set(AppState::MainMenu);
+        }
+    }
+
+    pub fn state(&self) -> AppState {
+        self.app.world.resource::<State<AppState>>().0
+    }
+
+    pub fn world(&self) -> &World {
+        &self.app.world
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.app.world
+    }
+
+    /// A simple bot that flaps whenever the bird falls under the next
+    /// gap. Skips the game over screen and starts a new run straight
+    /// away when in the menu.
+    pub fn autopilot(&mut self) -> bool {
+        match self.state() {
+            AppState::MainMenu => return true,
+            AppState::GameOver => {
+                self.leave_game_over();
+                return false;
+            }
+            _ => (),
+        }
+
+        let world = &mut self.app.world;
+
+        let (bird_position, bird_velocity, bird_collider) = world
+            .query_filtered::<(&Transform, &Velocity, &Collider), With<Bird>>()
+            .single(world);
+        let (bird_position, falling) = (bird_position.translation, bird_velocity.y <= 0.);
+        let bird_half_width = bird_collider.half_size().x;
+
+        // The closest gap the bird hasn't fully passed yet.
+        let next_gap = world
+            .query_filtered::<&Transform, With<PointMarker>>()
+            .iter(world)
+            .map(|transform| transform.translation)
+            .filter(|translation| translation.x + bird_half_width >= bird_position.x)
+            .min_by(|a, b| a.x.total_cmp(&b.x));
+
+        match next_gap {
+            Some(gap) => falling && bird_position.y < gap.y - AUTOPILOT_MARGIN,
+            None => falling && bird_position.y < 0.,
+        }
+    }
+}
+
+impl Default for Simulation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Plays `ticks` ticks with the autopilot and prints the score of every
+/// finished run.
+pub fn run(ticks: u64, plugin: SimulationPlugin) {
+    let mut simulation = Simulation::from_plugin(plugin);
+
+    let mut runs = 0;
+    let mut best = 0;
+    let mut run_score = 0;
+    let mut run_seed = simulation.seed();
+    let mut previous_state = simulation.state();
+
+    for _ in 0..ticks {
+        let flap = simulation.autopilot();
+        simulation.step(flap);
+
+        let state = simulation.state();
+
+        // The score is reset as soon as the run is left so the score of
+        // a run is tracked while it is still going.
+        if state == AppState::InGame {
+            run_score = run_score.max(simulation.score());
+        }
+
+        if previous_state == AppState::InGame && state == AppState::GameOver {
+            runs += 1;
+            best = best.max(run_score);
+
+            println!("Run {runs} (seed {run_seed}): {run_score}");
+            run_score = 0;
+            run_seed = simulation.seed();
+        }
+
+        previous_state = state;
+    }
+
+    println!("Simulated {ticks} ticks, {runs} finished runs, best score {best}");
+}
+
+/// Plays the replay of `plugin` back and returns the score it ended with.
+pub fn play_replay(plugin: SimulationPlugin) -> i128 {
+    let mut simulation = Simulation::from_plugin(plugin);
+
+    // Playback enters the run on the first tick and leaves it once the
+    // bird dies.
+    simulation.step(false);
+    while simulation.state() == AppState::InGame {
+        simulation.step(false);
+    }
+
+    simulation.replay().score
+}
+
+/// Plays the replay of `plugin` back and prints how its score compares
+/// to the recorded one.
+pub fn run_replay(plugin: SimulationPlugin) {
+    let recorded = plugin.replay
+        .as_ref()
+        .expect("Should be Some<T>")
+        .score;
+    let score = play_replay(plugin);
+
+    if score == recorded {
+        println!("Replay matched with a score of {score}");
+    } else {
+        println!("Replay ended with a score of {score} but {recorded} was recorded");
+    }
+}
@@ -0,0 +1,423 @@
+//! Player input as actions instead of raw keys.
+//!
+//! Systems ask [`ActionInput`] whether an [`Action`] was pressed and never
+//! look at keys or buttons themselves, so every control can be rebound.
+
+use bevy::{
+    input::gamepad::{GamepadConnection, GamepadConnectionEvent},
+    prelude::*,
+    utils::HashSet,
+};
+use serde::{Serialize, Deserialize};
+
+use super::save::SaveData;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Flap,
+    Pause,
+    Confirm,
+    Back,
+    Leaderboard,
+    Stats,
+    Settings,
+    ResetControls,
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Action {
+    pub const ALL: [Action; 12] = [
+        Action::Flap,
+        Action::Pause,
+        Action::Confirm,
+        Action::Back,
+        Action::Leaderboard,
+        Action::Stats,
+        Action::Settings,
+        Action::ResetControls,
+        Action::Up,
+        Action::Down,
+        Action::Left,
+        Action::Right,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Flap => "Flap",
+            Action::Pause => "Pause",
+            Action::Confirm => "Confirm",
+            Action::Back => "Back",
+            Action::Leaderboard => "Leaderboard",
+            Action::Stats => "Stats",
+            Action::Settings => "Settings",
+            Action::ResetControls => "Reset Controls",
+            Action::Up => "Up",
+            Action::Down => "Down",
+            Action::Left => "Left",
+            Action::Right => "Right",
+        }
+    }
+}
+
+/// A key, mouse button, gamepad button or touch an action can be bound
+/// to. Gamepad bindings work on every connected gamepad, a touch anywhere
+/// on the screen counts as a tap.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButtonType),
+    Touch,
+}
+
+impl Binding {
+    pub fn name(&self) -> String {
+        match self {
+            Binding::Key(key) => format!("{key:?}"),
+            Binding::Mouse(button) => format!("Mouse {button:?}"),
+            Binding::Gamepad(button) => format!("Pad {button:?}"),
+            Binding::Touch => "Tap".to_string(),
+        }
+    }
+}
+
+/// What every action is bound to.
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct InputBindings {
+    pub flap: Vec<Binding>,
+    pub pause: Vec<Binding>,
+    pub confirm: Vec<Binding>,
+    pub back: Vec<Binding>,
+    pub leaderboard: Vec<Binding>,
+    pub stats: Vec<Binding>,
+    pub settings: Vec<Binding>,
+    pub reset_controls: Vec<Binding>,
+    pub up: Vec<Binding>,
+    pub down: Vec<Binding>,
+    pub left: Vec<Binding>,
+    pub right: Vec<Binding>,
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        InputBindings {
+            flap: vec![
+                Binding::Key(KeyCode::Space),
+                Binding::Mouse(MouseButton::Left),
+                Binding::Gamepad(GamepadButtonType::South),
+                Binding::Touch,
+            ],
+            pause: vec![
+                Binding::Key(KeyCode::Escape),
+                Binding::Key(KeyCode::P),
+                Binding::Gamepad(GamepadButtonType::Start),
+            ],
+            confirm: vec![
+                Binding::Key(KeyCode::Return),
+                Binding::Key(KeyCode::Space),
+                Binding::Mouse(MouseButton::Left),
+                Binding::Gamepad(GamepadButtonType::South),
+                Binding::Touch,
+            ],
+            back: vec![
+                Binding::Key(KeyCode::Escape),
+                Binding::Gamepad(GamepadButtonType::East),
+            ],
+            leaderboard: vec![
+                Binding::Key(KeyCode::L),
+                Binding::Gamepad(GamepadButtonType::North),
+            ],
+            stats: vec![
+                Binding::Key(KeyCode::S),
+                Binding::Gamepad(GamepadButtonType::West),
+            ],
+            settings: vec![
+                Binding::Key(KeyCode::O),
+                Binding::Gamepad(GamepadButtonType::Select),
+            ],
+            reset_controls: vec![
+                Binding::Key(KeyCode::R),
+                Binding::Gamepad(GamepadButtonType::North),
+            ],
+            up: vec![
+                Binding::Key(KeyCode::Up),
+                Binding::Gamepad(GamepadButtonType::DPadUp),
+            ],
+            down: vec![
+                Binding::Key(KeyCode::Down),
+                Binding::Gamepad(GamepadButtonType::DPadDown),
+            ],
+            left: vec![
+                Binding::Key(KeyCode::Left),
+                Binding::Gamepad(GamepadButtonType::DPadLeft),
+            ],
+            right: vec![
+                Binding::Key(KeyCode::Right),
+                Binding::Gamepad(GamepadButtonType::DPadRight),
+            ],
+        }
+    }
+}
+
+impl InputBindings {
+    pub fn get(&self, action: Action) -> &[Binding] {
+        match action {
+            Action::Flap => &self.flap,
+            Action::Pause => &self.pause,
+            Action::Confirm => &self.confirm,
+            Action::Back => &self.back,
+            Action::Leaderboard => &self.leaderboard,
+            Action::Stats => &self.stats,
+            Action::Settings => &self.settings,
+            Action::ResetControls => &self.reset_controls,
+            Action::Up => &self.up,
+            Action::Down => &self.down,
+            Action::Left => &self.left,
+            Action::Right => &self.right,
+        }
+    }
+
+    pub fn get_mut(&mut self, action: Action) -> &mut Vec<Binding> {
+        match action {
+            Action::Flap => &mut self.flap,
+            Action::Pause => &mut self.pause,
+            Action::Confirm => &mut self.confirm,
+            Action::Back => &mut self.back,
+            Action::Leaderboard => &mut self.leaderboard,
+            Action::Stats => &mut self.stats,
+            Action::Settings => &mut self.settings,
+            Action::ResetControls => &mut self.reset_controls,
+            Action::Up => &mut self.up,
+            Action::Down => &mut self.down,
+            Action::Left => &mut self.left,
+            Action::Right => &mut self.right,
+        }
+    }
+
+    /// The bindings of `action` for hints, like `Space or Mouse Left`.
+    pub fn names(&self, action: Action) -> String {
+        self.get(action)
+            .iter()
+            .map(Binding::name)
+            .collect::<Vec<_>>()
+            .join(" or ")
+    }
+
+    /// Adds the default bindings `is_new` picks out to bindings saved
+    /// before those kinds of bindings existed.
+    pub fn add_defaults(&mut self, is_new: impl Fn(&Binding) -> bool) {
+        let defaults = InputBindings::default();
+
+        for action in Action::ALL {
+            let bindings = self.get_mut(action);
+
+            for binding in defaults.get(action) {
+                if is_new(binding) && !bindings.contains(binding) {
+                    bindings.push(*binding);
+                }
+            }
+        }
+    }
+
+    /// Binds `binding` to `action`, or unbinds it when it already is.
+    /// The last binding of an action is kept so it can't become
+    /// unreachable. Fails with the other action when `binding` already
+    /// does something else on a screen `action` is used on.
+    pub fn toggle(&mut self, action: Action, binding: Binding) -> Result<(), Action> {
+        if let Some(other) = self.conflict(action, binding) {
+            if !self.get(action).contains(&binding) {
+                return Err(other);
+            }
+        }
+
+        let bindings = self.get_mut(action);
+
+        match bindings.iter().position(|bound| *bound == binding) {
+            Some(index) if bindings.len() > 1 => {
+                bindings.remove(index);
+            }
+            Some(_) => (),
+            None => bindings.push(binding),
+        }
+
+        Ok(())
+    }
+
+    /// Another action bound to `binding` that is read on the same screen
+    /// as `action`.
+    pub fn conflict(&self, action: Action, binding: Binding) -> Option<Action> {
+        SCREENS
+            .iter()
+            .filter(|screen| screen.contains(&action))
+            .flat_map(|screen| screen.iter())
+            .find(|other| **other != action && self.get(**other).contains(&binding))
+            .copied()
+    }
+
+}
+
+// Actions read on the same screen, which can't share a binding. Pause and
+// Back both resume from the pause menu, so they are listed apart and can.
+const SCREENS: [&[Action]; 5] = [
+    // In game
+    &[Action::Flap, Action::Pause],
+    // Main menu
+    &[
+        Action::Flap,
+        Action::Left,
+        Action::Right,
+        Action::Leaderboard,
+        Action::Stats,
+        Action::Settings,
+    ],
+    // Pause menu
+    &[Action::Pause, Action::Up, Action::Down, Action::Confirm],
+    &[Action::Back, Action::Up, Action::Down, Action::Confirm],
+    // Settings
+    &[
+        Action::Up,
+        Action::Down,
+        Action::Left,
+        Action::Right,
+        Action::Confirm,
+        Action::Back,
+        Action::ResetControls,
+    ],
+];
+
+// Components, Resources, Events
+
+/// Actions pressed this frame.
+#[derive(Resource, Default, Debug)]
+pub struct ActionInput {
+    pressed: HashSet<Action>,
+    just_pressed: HashSet<Action>,
+}
+
+impl ActionInput {
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.just_pressed.contains(&action)
+    }
+}
+
+/// The set that fills [`ActionInput`], systems reading actions in
+/// `CoreSet::PreUpdate` run after it. Runs after the ui so a click or tap
+/// on a button isn't also a flap.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ActionSystem;
+
+// Initial Setup
+pub fn load_bindings(
+    mut commands: Commands,
+    save_data: Res<SaveData>,
+) {
+    let bindings = &save_data.bindings;
+
+    // Saves from before conflicts were refused can still have them.
+    for action in Action::ALL {
+        for binding in bindings.get(action) {
+            if let Some(other) = bindings.conflict(action, *binding) {
+                warn!("{} is bound to both {} and {}", binding.name(), action.name(), other.name());
+            }
+        }
+    }
+
+    commands.insert_resource(bindings.clone());
+}
+
+fn gamepad_just_pressed(
+    gamepads: &Gamepads,
+    gamepad_input: &Input<GamepadButton>,
+    button_type: GamepadButtonType,
+) -> bool {
+    gamepads
+        .iter()
+        .any(|gamepad| gamepad_input.just_pressed(GamepadButton::new(gamepad, button_type)))
+}
+
+fn gamepad_pressed(
+    gamepads: &Gamepads,
+    gamepad_input: &Input<GamepadButton>,
+    button_type: GamepadButtonType,
+) -> bool {
+    gamepads
+        .iter()
+        .any(|gamepad| gamepad_input.pressed(GamepadButton::new(gamepad, button_type)))
+}
+
+// Turns the keys and buttons pressed this frame into actions
+pub fn update_actions(
+    keyboard_input: Res<Input<KeyCode>>,
+    mouse_input: Res<Input<MouseButton>>,
+    gamepads: Res<Gamepads>,
+    gamepad_input: Res<Input<GamepadButton>>,
+    touches: Res<Touches>,
+    button_query: Query<&Interaction, With<Button>>,
+    bindings: Res<InputBindings>,
+    mut action_input: ResMut<ActionInput>,
+) {
+    action_input.pressed.clear();
+    action_input.just_pressed.clear();
+
+    // Buttons handle their own clicks and taps.
+    let on_button = button_query
+        .iter()
+        .any(|interaction| *interaction == Interaction::Clicked);
+
+    for action in Action::ALL {
+        for binding in bindings.get(action) {
+            let (pressed, just_pressed) = match binding {
+                Binding::Mouse(_) | Binding::Touch if on_button => (false, false),
+                Binding::Key(key) => (
+                    keyboard_input.pressed(*key),
+                    keyboard_input.just_pressed(*key),
+                ),
+                Binding::Mouse(button) => (
+                    mouse_input.pressed(*button),
+                    mouse_input.just_pressed(*button),
+                ),
+                Binding::Gamepad(button_type) => (
+                    gamepad_pressed(&gamepads, &gamepad_input, *button_type),
+                    gamepad_just_pressed(&gamepads, &gamepad_input, *button_type),
+                ),
+                Binding::Touch => (
+                    touches.iter().next().is_some(),
+                    touches.any_just_pressed(),
+                ),
+            };
+
+            if pressed {
+                action_input.pressed.insert(action);
+            }
+
+            if just_pressed {
+                action_input.just_pressed.insert(action);
+            }
+        }
+    }
+}
+
+// Gamepads can be plugged in and out at any time, bevy keeps track of
+// them in `Gamepads` so this only reports it
+pub fn log_gamepad_connections(
+    mut connection_events: EventReader<GamepadConnectionEvent>,
+) {
+    for event in connection_events.iter() {
+        match &event.connection {
+            GamepadConnection::Connected(info) => {
+                info!("Gamepad {} connected: {}", event.gamepad.id, info.name);
+            }
+            GamepadConnection::Disconnected => {
+                info!("Gamepad {} disconnected", event.gamepad.id);
+            }
+        }
+    }
+}
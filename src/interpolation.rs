@@ -0,0 +1,59 @@
+//! Smooths out movement between fixed ticks.
+//!
+//! The game moves in fixed ticks which rarely line up with frames, so
+//! drawing `Transform` as is would stutter. Every moving entity is drawn
+//! between where it was last tick and where it is now, by how far the
+//! clock is into the next tick. `Transform` itself is left alone for the
+//! simulation, only `GlobalTransform` is changed.
+
+use bevy::prelude::*;
+
+use super::Velocity;
+
+// Components, Resources, Events
+/// Where an entity was at the start of the current tick.
+#[derive(Component, Deref, DerefMut)]
+pub struct PreviousTransform(pub Transform);
+
+// Runs before every tick, paused or not, so a still entity is never drawn
+// moving
+pub fn record_previous_transforms(
+    mut commands: Commands,
+    mut query: Query<(Entity, &Transform, Option<&mut PreviousTransform>), With<Velocity>>,
+) {
+    for (entity, transform, previous) in &mut query {
+        match previous {
+            Some(mut previous) => **previous = *transform,
+            None => {
+                commands.entity(entity).insert(PreviousTransform(*transform));
+            }
+        }
+    }
+}
+
+// Draws moving entities part of the way to where the last tick put them
+pub fn interpolate_transforms(
+    time: Res<Time>,
+    fixed_time: Res<FixedTime>,
+    mut query: Query<(&Transform, &PreviousTransform, &mut GlobalTransform)>,
+) {
+    let period = fixed_time.period.as_secs_f32();
+    if period <= 0. {
+        return;
+    }
+
+    // A frozen clock shows exactly where the last tick left things.
+    let alpha = if time.is_paused() {
+        1.
+    } else {
+        (fixed_time.accumulated().as_secs_f32() / period).clamp(0., 1.)
+    };
+
+    for (transform, previous, mut global_transform) in &mut query {
+        *global_transform = GlobalTransform::from(Transform {
+            translation: previous.translation.lerp(transform.translation, alpha),
+            rotation: previous.rotation.slerp(transform.rotation, alpha),
+            scale: transform.scale,
+        });
+    }
+}
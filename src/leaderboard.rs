@@ -0,0 +1,201 @@
+//! The local leaderboard kept in the save. Every difficulty has its own
+//! top ten.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Serialize, Deserialize};
+
+// Constants
+pub const LEADERBOARD_SIZE: usize = 10;
+pub const NAME_MAX_LENGTH: usize = 12;
+
+// Mode of entries set before there were difficulties.
+pub const DEFAULT_MODE: &str = "Normal";
+
+// Name given to entries without one.
+pub const UNKNOWN_NAME: &str = "???";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LeaderboardEntry {
+    pub name: String,
+    pub score: i128,
+    /// Seconds since the unix epoch. Unknown for scores migrated from
+    /// before the leaderboard existed.
+    pub date: Option<u64>,
+    pub seed: Option<u64>,
+    /// Name of the difficulty the run was played on.
+    pub mode: String,
+}
+
+impl LeaderboardEntry {
+    /// An entry for a run that has just finished.
+    pub fn new(name: String, score: i128, seed: u64, mode: &str) -> Self {
+        let date = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|since_epoch| since_epoch.as_secs())
+            .ok();
+
+        LeaderboardEntry {
+            name,
+            score,
+            date,
+            seed: Some(seed),
+            mode: mode.to_string(),
+        }
+    }
+
+    /// The date as `YYYY-MM-DD`, or `-` when it is unknown.
+    pub fn date_text(&self) -> String {
+        match self.date {
+            Some(date) => format_date(date),
+            None => "-".to_string(),
+        }
+    }
+}
+
+/// The best runs of every mode, highest score first.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(transparent)]
+pub struct Leaderboard {
+    entries: Vec<LeaderboardEntry>,
+}
+
+impl Leaderboard {
+    /// The entries of `mode`, highest score first.
+    pub fn entries<'a>(&'a self, mode: &'a str) -> impl Iterator<Item = &'a LeaderboardEntry> {
+        self.entries
+            .iter()
+            .filter(move |entry| entry.mode == mode)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Whether a run with `score` would get onto the leaderboard of `mode`.
+    pub fn qualifies(&self, score: i128, mode: &str) -> bool {
+        if score <= 0 {
+            return false;
+        }
+
+        // Only a full board has a lowest entry to beat.
+        match self.entries(mode).nth(LEADERBOARD_SIZE - 1) {
+            Some(lowest) => score > lowest.score,
+            None => true,
+        }
+    }
+
+    /// Adds `entry` in its place and returns its rank within its mode,
+    /// starting at 0. Ties go to whoever got the score first.
+    pub fn insert(&mut self, entry: LeaderboardEntry) -> Option<usize> {
+        if !self.qualifies(entry.score, &entry.mode) {
+            return None;
+        }
+
+        let rank = self.entries(&entry.mode)
+            .position(|other| entry.score > other.score)
+            .unwrap_or(self.entries(&entry.mode).count());
+
+        // Placed before the first lower score of any mode, which keeps
+        // every mode sorted on its own.
+        let index = self.entries
+            .iter()
+            .position(|other| entry.score > other.score)
+            .unwrap_or(self.entries.len());
+
+        let mode = entry.mode.clone();
+        self.entries.insert(index, entry);
+
+        // Drops the lowest entry of the mode once it has one too many.
+        if self.entries(&mode).count() > LEADERBOARD_SIZE {
+            let lowest = self.entries
+                .iter()
+                .rposition(|other| other.mode == mode)
+                .expect("Should be Some<T>");
+
+            self.entries.remove(lowest);
+        }
+
+        Some(rank)
+    }
+}
+
+// Converts seconds since the unix epoch into a `YYYY-MM-DD` date.
+// Uses Howard Hinnant's days to civil algorithm.
+fn format_date(seconds: u64) -> String {
+    let days = (seconds / 86_400) as i64 + 719_468;
+
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, score: i128, mode: &str) -> LeaderboardEntry {
+        LeaderboardEntry::new(name.to_string(), score, 0, mode)
+    }
+
+    fn scores(leaderboard: &Leaderboard, mode: &str) -> Vec<i128> {
+        leaderboard
+            .entries(mode)
+            .map(|entry| entry.score)
+            .collect()
+    }
+
+    #[test]
+    fn keeps_the_top_ten() {
+        let mut leaderboard = Leaderboard::default();
+        for score in 1..=12 {
+            leaderboard.insert(entry("A", score, "Normal"));
+        }
+
+        assert_eq!(scores(&leaderboard, "Normal"), (3..=12).rev().collect::<Vec<_>>());
+        assert!(!leaderboard.qualifies(3, "Normal"));
+        assert_eq!(leaderboard.insert(entry("B", 2, "Normal")), None);
+        assert_eq!(leaderboard.insert(entry("B", 20, "Normal")), Some(0));
+        assert_eq!(scores(&leaderboard, "Normal").len(), LEADERBOARD_SIZE);
+        assert_eq!(leaderboard.entries("Normal").last().map(|entry| entry.score), Some(4));
+    }
+
+    #[test]
+    fn ties_go_to_the_earlier_score() {
+        let mut leaderboard = Leaderboard::default();
+        leaderboard.insert(entry("First", 5, "Normal"));
+        leaderboard.insert(entry("Higher", 8, "Normal"));
+
+        assert_eq!(leaderboard.insert(entry("Second", 5, "Normal")), Some(2));
+
+        let names: Vec<_> = leaderboard
+            .entries("Normal")
+            .map(|entry| entry.name.as_str())
+            .collect();
+        assert_eq!(names, ["Higher", "First", "Second"]);
+    }
+
+    #[test]
+    fn modes_are_ranked_separately() {
+        let mut leaderboard = Leaderboard::default();
+        for score in 1..=LEADERBOARD_SIZE as i128 {
+            leaderboard.insert(entry("A", score * 10, "Hard"));
+        }
+
+        // A full Hard board doesn't keep low scores off Normal.
+        assert!(leaderboard.qualifies(1, "Normal"));
+        assert_eq!(leaderboard.insert(entry("B", 1, "Normal")), Some(0));
+        assert_eq!(leaderboard.insert(entry("C", 55, "Hard")), Some(5));
+
+        assert_eq!(scores(&leaderboard, "Normal"), [1]);
+        assert_eq!(scores(&leaderboard, "Hard"), [100, 90, 80, 70, 60, 55, 50, 40, 30, 20]);
+    }
+}
@@ -0,0 +1,499 @@
+//! A version of flappy bird
+//!
+//! The game is split into two plugins. [`SimulationPlugin`] holds all of
+//! the gameplay and only needs `MinimalPlugins` to run, so it can be stepped
+//! without a window (see [`headless`]). [`PresentationPlugin`] adds sprites,
+//! sounds, ui and keyboard/mouse input on top of it.
+
+// Bevy queries and system parameter lists get long quickly and splitting
+// them up mostly hides what a system touches.
+#![allow(clippy::type_complexity, clippy::too_many_arguments)]
+
+use bevy::{
+    audio::AddAudioSource,
+    prelude::*,
+    render::view::VisibilitySystems,
+    transform::TransformSystem,
+};
+
+pub mod audio;
+pub mod bird;
+pub mod pipes;
+pub mod camera;
+pub mod collision;
+pub mod game_over;
+pub mod game_ui;
+#[cfg(feature = "debug")]
+pub mod debug;
+pub mod difficulty;
+pub mod headless;
+pub mod input;
+pub mod interpolation;
+pub mod leaderboard;
+pub mod loading;
+pub mod music;
+pub mod replay;
+pub mod save;
+pub mod stats;
+pub mod tuning;
+
+/// Constants
+pub const TEXT_COLOR: Color = Color::rgb(0.9, 0.9, 1.0);
+pub const BACKGROUND_COLOR: Color = Color::rgb(0.2, 0.5, 0.9);
+
+/// Gameplay without any rendering, audio or window access.
+///
+/// Every system that changes the state of the game runs in
+/// `CoreSchedule::FixedUpdate` so one fixed tick is one step of the game.
+#[derive(Default)]
+pub struct SimulationPlugin {
+    /// Plays the course of this seed on every run. A random course is
+    /// played each run when unset.
+    pub seed: Option<u64>,
+    /// Plays this replay back straight away. Its seed and tuning replace
+    /// `seed` and `tuning` until it is over.
+    pub replay: Option<replay::Replay>,
+    /// Physics and course values to start with, as read from the
+    /// tuning file.
+    pub tuning: tuning::Tuning,
+    /// Replaced by the difficulty of `replay` when there is one.
+    pub difficulty: difficulty::Difficulty,
+}
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        let seed = self.replay
+            .as_ref()
+            .map(|replay| replay.seed)
+            .or(self.seed);
+
+        let course_rng = match seed {
+            Some(seed) => pipes::CourseRng::fixed(seed),
+            None => pipes::CourseRng::random(),
+        };
+
+        let difficulty = self.replay
+            .as_ref()
+            .map_or(self.difficulty, |replay| replay.difficulty);
+
+        // Replays play with the tuning they were recorded with, so edits
+        // to the tuning file don't change how they end.
+        let base_tuning = self.replay
+            .as_ref()
+            .and_then(|replay| replay.tuning.clone())
+            .unwrap_or_else(|| self.tuning.clone());
+        let tuning = difficulty.tuning(&base_tuning);
+
+        app
+            .add_state::<AppState>()
+            .add_state::<PauseState>();
+
+        if let Some(replay) = &self.replay {
+            app
+                .insert_resource(replay::ReplayPlayback::new(replay.clone(), self.tuning.clone(), self.seed))
+                .add_startup_system(replay::start_playback)
+                .add_system(replay::start_playback.in_schedule(OnExit(AppState::Loading)));
+        }
+
+        app
+            .insert_resource(FixedTime::new(tuning.fixed_period()))
+            .insert_resource(tuning::BaseTuning(base_tuning))
+            .insert_resource(tuning)
+            .insert_resource(difficulty)
+            .insert_resource(course_rng)
+            .insert_resource(game_ui::scoreboard::Scoreboard { score: 0 })
+            .init_resource::<bird::FlapInput>()
+            .init_resource::<replay::RunTick>()
+            .init_resource::<replay::ReplayRecorder>()
+            .init_resource::<camera::VisibleArea>()
+
+            .add_event::<bird::BirdCollisionEvent>()
+            .add_event::<bird::BirdJumpEvent>()
+            .add_event::<bird::BirdPointEvent>()
+            .add_event::<game_over::GameOverEvent>()
+
+            .add_system(tuning::apply_time_step.run_if(resource_changed::<tuning::Tuning>()))
+
+            .add_startup_system(bird::setup)
+            .add_startup_system(pipes::setup)
+
+            .add_system(replay::begin_run.in_schedule(OnEnter(AppState::InGame)))
+            .add_system(replay::end_playback.in_schedule(OnExit(AppState::InGame)))
+            .add_system(game_over::reset_run.in_schedule(OnExit(AppState::InGame)))
+
+            // Everything in a tick runs in a fixed order so that every run
+            // of the same inputs gives the same result.
+            .edit_schedule(CoreSchedule::FixedUpdate, |schedule| {
+                schedule.configure_sets(
+                    (
+                        SimulationSet::Input,
+                        SimulationSet::Movement,
+                        SimulationSet::Collision,
+                        SimulationSet::GameOver,
+                    ).chain()
+                );
+
+                // Nothing in the game moves on while paused.
+                for set in [
+                    SimulationSet::Input,
+                    SimulationSet::Movement,
+                    SimulationSet::Collision,
+                    SimulationSet::GameOver,
+                ] {
+                    schedule.configure_set(set.run_if(in_state(PauseState::Running)));
+                }
+            })
+            .add_systems(
+                (
+                    // State changes queued by the previous tick are applied
+                    // here so that a frame running several ticks never runs
+                    // one of them in a stale state.
+                    apply_state_transition::<AppState>,
+
+                    difficulty::apply_difficulty,
+                    bird::apply_bird_tuning.run_if(resource_changed::<tuning::Tuning>()),
+                    pipes::rebuild_course,
+
+                    replay::play_flaps.run_if(in_state(AppState::InGame)),
+                    replay::record_flaps.run_if(in_state(AppState::InGame)),
+
+                    bird::game_start.run_if(in_state(AppState::MainMenu)),
+                    bird::idle_bird_jump.run_if(not(in_state(AppState::InGame))),
+                    bird::move_bird.run_if(in_state(AppState::InGame)),
+                )
+                .chain()
+                .in_set(SimulationSet::Input)
+                .in_schedule(CoreSchedule::FixedUpdate),
+            )
+            .add_systems(
+                (
+                    bird::apply_bird_gravity,
+
+                    pipes::move_pipes.run_if(in_state(AppState::InGame)),
+
+                    bird::apply_bird_velocity,
+                    pipes::apply_pipes_velocity,
+                    bird::rotate_bird,
+
+                    pipes::spawn_pipes,
+                    pipes::despawn_pipes,
+                )
+                .chain()
+                .in_set(SimulationSet::Movement)
+                .in_schedule(CoreSchedule::FixedUpdate),
+            )
+            .add_systems(
+                (
+                    bird::bird_pipe_collisions,
+                    bird::bird_point_collisions,
+                    bird::bird_boundary_collisions,
+                )
+                .chain()
+                .distributive_run_if(in_state(AppState::InGame))
+                .in_set(SimulationSet::Collision)
+                .in_schedule(CoreSchedule::FixedUpdate),
+            )
+            .add_systems(
+                (
+                    replay::record_score,
+                    game_over::game_over,
+                    replay::advance_run_tick,
+                )
+                .chain()
+                .distributive_run_if(in_state(AppState::InGame))
+                .in_set(SimulationSet::GameOver)
+                .in_schedule(CoreSchedule::FixedUpdate),
+            );
+    }
+}
+
+/// The parts of a fixed tick, run in this order.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SimulationSet {
+    Input,
+    Movement,
+    Collision,
+    GameOver,
+}
+
+/// Sprites, sounds, ui and player input. Needs `DefaultPlugins` and
+/// [`SimulationPlugin`].
+pub struct PresentationPlugin;
+
+impl Plugin for PresentationPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            // The simulation starts in the menu, with sprites and sounds
+            // to load the game waits for them first.
+            .insert_resource(State(AppState::Loading))
+            .init_resource::<loading::LoadingProgress>()
+
+            .insert_resource(game_ui::high_score::HighScore { highscore: 0 })
+            .insert_resource(game_ui::fps::FpsSpawned(false))
+            .insert_resource(game_ui::high_score::HighScoreSpawned(false))
+            .insert_resource(ClearColor(BACKGROUND_COLOR))
+            .init_resource::<game_ui::name_entry::PendingEntry>()
+            .init_resource::<stats::RunStats>()
+            .init_resource::<game_ui::pause::PauseSelection>()
+            .init_resource::<game_ui::settings::SettingsMenu>()
+            .init_resource::<input::ActionInput>()
+            .add_event::<audio::PlaySound>()
+            .init_resource::<music::MusicPlayer>()
+
+            .add_asset::<tuning::Tuning>()
+            .init_asset_loader::<tuning::TuningLoader>()
+            .add_audio_source::<music::MusicTrack>()
+            .init_asset_loader::<music::TrackLoader>()
+
+            .add_startup_system(save::load_save.in_base_set(StartupSet::PreStartup))
+            .add_startup_system(input::load_bindings)
+            .add_startup_system(audio::setup)
+            .add_startup_system(music::setup)
+            .add_startup_system(loading::setup)
+            .add_startup_system(tuning::setup)
+            .add_startup_system(difficulty::load_difficulty)
+            .add_startup_system(camera::setup)
+            .add_startup_system(game_ui::setup)
+
+            .add_system(game_ui::loading::setup.in_schedule(OnEnter(AppState::Loading)))
+            .add_system(game_ui::loading::remove_loading_screen.in_schedule(OnExit(AppState::Loading)))
+            .add_system(loading::check_sprite_sizes.in_schedule(OnExit(AppState::Loading)))
+            .add_systems(
+                (
+                    loading::check_loading,
+                    game_ui::loading::update_loading_screen.after(loading::check_loading),
+                )
+                .in_set(OnUpdate(AppState::Loading))
+            )
+
+            .add_system(game_ui::menu::setup.in_schedule(OnEnter(AppState::MainMenu)))
+            .add_system(game_ui::menu::remove_menu_text.in_schedule(OnExit(AppState::MainMenu)))
+
+            .add_system(game_ui::fps::setup.in_schedule(OnEnter(AppState::MainMenu)))
+
+            .add_system(game_ui::high_score::setup.in_schedule(OnEnter(AppState::MainMenu))
+                .after(game_ui::menu::setup)
+            )
+
+            .add_system(game_ui::high_score::load_high_score.in_schedule(OnEnter(AppState::MainMenu)))
+            .add_system(game_ui::high_score::load_high_score
+                .run_if(resource_changed::<difficulty::Difficulty>())
+            )
+            .add_system(game_ui::high_score::save_high_score.in_schedule(OnExit(AppState::InGame))
+                .before(replay::end_playback)
+            )
+
+            .add_system(game_ui::scoreboard::setup.in_schedule(OnEnter(AppState::InGame)))
+            .add_system(game_ui::scoreboard::remove_scoreboard_text.in_schedule(OnExit(AppState::InGame)))
+
+            .add_system(game_ui::game_over::setup.in_schedule(OnEnter(AppState::GameOver)))
+            .add_system(game_ui::game_over::remove_game_over_text.in_schedule(OnExit(AppState::GameOver)))
+
+            .add_system(game_ui::name_entry::setup.in_schedule(OnEnter(AppState::NameEntry)))
+            .add_system(game_ui::name_entry::clear_typed_characters.in_schedule(OnEnter(AppState::NameEntry)))
+            .add_system(game_ui::name_entry::remove_name_entry_text.in_schedule(OnExit(AppState::NameEntry)))
+
+            .add_system(game_ui::leaderboard::setup.in_schedule(OnEnter(AppState::Leaderboard)))
+            .add_system(game_ui::leaderboard::remove_leaderboard_text.in_schedule(OnExit(AppState::Leaderboard)))
+
+            .add_system(game_ui::stats::setup.in_schedule(OnEnter(AppState::Stats)))
+            .add_system(game_ui::stats::remove_stats_text.in_schedule(OnExit(AppState::Stats)))
+
+            .add_system(game_ui::settings::setup.in_schedule(OnEnter(AppState::Settings)))
+            .add_system(game_ui::settings::remove_settings_text.in_schedule(OnExit(AppState::Settings)))
+
+            .add_system(game_ui::name_entry::check_new_entry
+                .after(game_over::game_over)
+                .run_if(in_state(AppState::InGame))
+                .in_set(SimulationSet::GameOver)
+                .in_schedule(CoreSchedule::FixedUpdate)
+            )
+            .add_system(game_ui::game_over::record_summary
+                .after(game_over::game_over)
+                .run_if(in_state(AppState::InGame))
+                .in_set(SimulationSet::GameOver)
+                .in_schedule(CoreSchedule::FixedUpdate)
+            )
+            .add_system(interpolation::record_previous_transforms
+                .before(SimulationSet::Input)
+                .in_schedule(CoreSchedule::FixedUpdate)
+            )
+            .add_system(interpolation::interpolate_transforms
+                .in_base_set(CoreSet::PostUpdate)
+                .after(TransformSystem::TransformPropagate)
+                .before(VisibilitySystems::CheckVisibility)
+            )
+
+            .add_system(stats::reset_run_stats.in_schedule(OnEnter(AppState::InGame)))
+            .add_system(stats::count_run_stats
+                .before(game_over::game_over)
+                .in_set(SimulationSet::GameOver)
+                .in_schedule(CoreSchedule::FixedUpdate)
+            )
+            .add_system(stats::record_run
+                .after(game_over::game_over)
+                .in_set(SimulationSet::GameOver)
+                .in_schedule(CoreSchedule::FixedUpdate)
+            )
+
+            .add_system(replay::save_replay.in_schedule(OnExit(AppState::InGame))
+                .before(replay::end_playback)
+            )
+
+            // Read straight after bevy updates its input resources so the
+            // fixed ticks of the same frame can already see the flap. Other
+            // states use the keys for their own menus.
+            .add_system(input::update_actions
+                .in_base_set(CoreSet::PreUpdate)
+                .after(bevy::input::InputSystem)
+                .after(bevy::ui::UiSystem::Focus)
+                .in_set(input::ActionSystem)
+            )
+            .add_system(bird::read_flap_input
+                .in_base_set(CoreSet::PreUpdate)
+                .after(input::ActionSystem)
+                .run_if(|state: Res<State<AppState>>| {
+                    matches!(state.0, AppState::MainMenu | AppState::InGame)
+                })
+                .run_if(in_state(PauseState::Running))
+            )
+
+            .add_system(game_ui::pause::setup.in_schedule(OnEnter(PauseState::Paused)))
+            .add_system(game_ui::pause::remove_pause_menu.in_schedule(OnExit(PauseState::Paused)))
+            .add_system(game_ui::pause::setup_pause_button.in_schedule(OnEnter(AppState::InGame)))
+            .add_system(game_ui::pause::remove_pause_button.in_schedule(OnExit(AppState::InGame)))
+            .add_system(game_ui::pause::pause_game
+                .in_set(OnUpdate(AppState::InGame))
+                .run_if(in_state(PauseState::Running))
+            )
+            .add_system(game_ui::pause::pause_on_disconnect
+                .in_set(OnUpdate(AppState::InGame))
+                .run_if(in_state(PauseState::Running))
+            )
+            .add_system(game_ui::pause::pause_menu.in_set(OnUpdate(PauseState::Paused)))
+
+            .add_systems(
+                (
+                    bird::add_sprite,
+                    pipes::add_sprites,
+
+                    bird::bird_jump_sound,
+                    bird::bird_point_sound,
+                    game_over::game_over_sound,
+                    audio::play_sounds
+                        .after(bird::bird_jump_sound)
+                        .after(bird::bird_point_sound)
+                        .after(game_over::game_over_sound),
+                    music::play_music
+                        .after(game_over::game_over_sound),
+
+                    save::write_save,
+
+                    input::log_gamepad_connections,
+
+                    game_ui::update_top_margins,
+
+                    tuning::reload_tuning,
+                )
+            )
+            // Their text is spawned on first entering the menu.
+            .add_systems(
+                (
+                    game_ui::fps::update_fps,
+                    game_ui::high_score::update_highscore_text,
+                )
+                .distributive_run_if(|state: Res<State<AppState>>| state.0 != AppState::Loading)
+            )
+            .add_systems(
+                (
+                    game_ui::scoreboard::update_scoreboard,
+
+                    game_ui::high_score::update_highscore,
+                )
+                .in_set(OnUpdate(AppState::InGame))
+            )
+            .add_system(game_ui::leaderboard::open_leaderboard.in_set(OnUpdate(AppState::MainMenu)))
+            .add_system(game_ui::leaderboard::close_leaderboard.in_set(OnUpdate(AppState::Leaderboard)))
+            .add_system(game_ui::stats::open_stats.in_set(OnUpdate(AppState::MainMenu)))
+            .add_system(game_ui::menu::change_difficulty.in_set(OnUpdate(AppState::MainMenu)))
+            .add_system(game_ui::stats::close_stats.in_set(OnUpdate(AppState::Stats)))
+            .add_system(game_ui::settings::open_settings.in_set(OnUpdate(AppState::MainMenu)))
+            .add_system(game_ui::settings::settings_menu.in_set(OnUpdate(AppState::Settings)))
+            .add_system(game_ui::game_over::leave_game_over.in_set(OnUpdate(AppState::GameOver)))
+            .add_system(game_ui::name_entry::type_name.in_set(OnUpdate(AppState::NameEntry)));
+    }
+}
+
+// Components & Resources used by more then one module
+/// In units per second.
+#[derive(Component, Deref, DerefMut, Debug)]
+pub struct Velocity(pub Vec2);
+
+/// The shape an entity collides with, centered on its translation.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub enum Collider {
+    Circle { radius: f32 },
+    Rect { size: Vec2 },
+}
+
+impl Collider {
+    /// The whole of a `texture_size` texture drawn at `scale`.
+    pub fn rect_from_texture(texture_size: Vec2, scale: Vec3) -> Self {
+        Collider::Rect {
+            size: texture_size * scale.truncate().abs(),
+        }
+    }
+
+    /// The largest circle that fits in a `texture_size` texture drawn at
+    /// `scale`.
+    pub fn circle_from_texture(texture_size: Vec2, scale: Vec3) -> Self {
+        let size = texture_size * scale.truncate().abs();
+
+        Collider::Circle {
+            radius: size.min_element() / 2.,
+        }
+    }
+
+    /// Half the size of the smallest rectangle around the shape.
+    pub fn half_size(&self) -> Vec2 {
+        match *self {
+            Collider::Circle { radius } => Vec2::splat(radius),
+            Collider::Rect { size } => size / 2.,
+        }
+    }
+
+    /// The same shape with `margin` taken off every side.
+    pub fn shrunk(&self, margin: f32) -> Self {
+        match *self {
+            Collider::Circle { radius } => Collider::Circle {
+                radius: (radius - margin).max(0.),
+            },
+            Collider::Rect { size } => Collider::Rect {
+                size: (size - margin * 2.).max(Vec2::ZERO),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, Default, PartialEq, Hash, States)]
+pub enum AppState {
+    /// Waits for assets, only with [`PresentationPlugin`].
+    Loading,
+    #[default]
+    MainMenu,
+    InGame,
+    GameOver,
+    NameEntry,
+    Leaderboard,
+    Stats,
+    Settings,
+}
+
+/// Whether a run is paused. Kept apart from [`AppState`] so pausing
+/// doesn't count as leaving the run.
+#[derive(Debug, Clone, Copy, Eq, Default, PartialEq, Hash, States)]
+pub enum PauseState {
+    #[default]
+    Running,
+    Paused,
+}
@@ -0,0 +1,120 @@
+//! Waits for every asset the game needs before showing the menu.
+//!
+//! Only used with [`crate::PresentationPlugin`], a headless game has
+//! nothing to load and starts straight in the menu.
+
+use bevy::{
+    asset::LoadState,
+    prelude::*,
+};
+
+use super::{
+    AppState,
+    audio::SoundCue,
+    bird::BIRD_SPRITE,
+    bird::BIRD_TEXTURE_SIZE,
+    game_ui::FONT,
+    music::MusicCue,
+    pipes::PIPE_SPRITE,
+    pipes::PIPE_TEXTURE_SIZE,
+};
+
+// Components, Resources, Events
+
+/// Everything loading waits for, by path. The handles are kept so the
+/// assets stay loaded.
+#[derive(Resource, Default)]
+pub struct LoadingAssets(Vec<(&'static str, HandleUntyped)>);
+
+/// How far loading has come.
+#[derive(Resource, Default, Debug)]
+pub struct LoadingProgress {
+    pub loaded: usize,
+    pub total: usize,
+    /// The first asset that couldn't be loaded.
+    pub failed: Option<&'static str>,
+}
+
+impl LoadingProgress {
+    /// From 0 to 1.
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 {
+            1.
+        } else {
+            self.loaded as f32 / self.total as f32
+        }
+    }
+}
+
+// Initial Setup
+pub fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+) {
+    let paths = [BIRD_SPRITE, PIPE_SPRITE, FONT]
+        .into_iter()
+        .chain(SoundCue::ALL.iter().map(SoundCue::path))
+        .chain(MusicCue::ALL.iter().map(MusicCue::path));
+
+    let handles = paths
+        .map(|path| (path, asset_server.load_untyped(path)))
+        .collect();
+
+    commands.insert_resource(LoadingAssets(handles));
+}
+
+// Counts the loaded assets and moves on to the menu once all of them are.
+// A failed asset stops loading for good.
+pub fn check_loading(
+    asset_server: Res<AssetServer>,
+    loading_assets: Res<LoadingAssets>,
+    mut progress: ResMut<LoadingProgress>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if progress.failed.is_some() {
+        return;
+    }
+
+    let mut loaded = 0;
+    for (path, handle) in &loading_assets.0 {
+        match asset_server.get_load_state(handle) {
+            LoadState::Loaded => loaded += 1,
+            LoadState::Failed => {
+                error!("Couldn't load {path}");
+                progress.failed = Some(path);
+                return;
+            }
+            _ => (),
+        }
+    }
+
+    if progress.loaded != loaded || progress.total != loading_assets.0.len() {
+        progress.loaded = loaded;
+        progress.total = loading_assets.0.len();
+    }
+
+    if loaded == loading_assets.0.len() {
+        next_state.set(AppState::MainMenu);
+    }
+}
+
+// Colliders are sized from the textures without loading them, so the
+// simulation can run headless. Warns when a sprite has been redrawn at a
+// different size.
+pub fn check_sprite_sizes(
+    asset_server: Res<AssetServer>,
+    images: Res<Assets<Image>>,
+) {
+    for (path, texture_size) in [
+        (BIRD_SPRITE, BIRD_TEXTURE_SIZE),
+        (PIPE_SPRITE, PIPE_TEXTURE_SIZE),
+    ] {
+        let Some(image) = images.get(&asset_server.get_handle(path)) else {
+            continue;
+        };
+
+        if image.size() != texture_size {
+            warn!("{path} is {} but its collider expects {texture_size}", image.size());
+        }
+    }
+}
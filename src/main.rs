@@ -1,114 +1,82 @@
-/// A version of flappy bird
-
 use bevy::{prelude::*, diagnostic::FrameTimeDiagnosticsPlugin};
 
-mod bird;
-mod pipes;
-mod camera;
-mod game_over;
-mod game_ui;
-mod debug;
+use flappy_qube::{
+    SimulationPlugin,
+    PresentationPlugin,
+    headless,
+    difficulty::Difficulty,
+    replay::Replay,
+    tuning::Tuning,
+};
 
-/// Constants
-const TIME_STEP: f32 = 1. / 60.;
-const GRAVITY: f32 = -40.;
-const TEXT_COLOR: Color = Color::rgb(0.9, 0.9, 1.0);
-const BACKGROUND_COLOR: Color = Color::rgb(0.2, 0.5, 0.9);
+// Ticks simulated by --headless when --ticks isn't given, one minute of play.
+const DEFAULT_HEADLESS_TICKS: u64 = 60 * 60;
 
 fn main() {
-    App::new()
-        .add_plugins(DefaultPlugins.set(
+    let args: Vec<String> = std::env::args().collect();
+
+    let seed = arg_value(&args, "--seed")
+        .map(|seed| seed.parse().expect("--seed should be a whole number"));
+
+    // Only used by headless runs, the game starts on the difficulty picked
+    // in the menu last time.
+    let difficulty = arg_value(&args, "--difficulty")
+        .map(|difficulty| difficulty.parse().unwrap_or_else(|error| panic!("--difficulty: {error}")))
+        .unwrap_or(Difficulty::Normal);
+
+    let replay = arg_value(&args, "--replay").map(|path| {
+        Replay::load(path).unwrap_or_else(|error| panic!("Couldn't load {path}: {error}"))
+    });
+
+    // Read up front so headless runs use it too. The game keeps going
+    // with the built in values when it is missing or broken.
+    let tuning_path = Tuning::default_path();
+    let tuning = Tuning::load(&tuning_path).unwrap_or_else(|error| {
+        eprintln!("Couldn't load {}: {error}", tuning_path.display());
+        Tuning::default()
+    });
+
+    if args.iter().any(|arg| arg == "--headless") {
+        if replay.is_some() {
+            headless::run_replay(SimulationPlugin { seed, replay, tuning, difficulty });
+            return;
+        }
+
+        let ticks = arg_value(&args, "--ticks")
+            .map(|ticks| ticks.parse().expect("--ticks should be a whole number"))
+            .unwrap_or(DEFAULT_HEADLESS_TICKS);
+
+        headless::run(ticks, SimulationPlugin { seed, replay, tuning, difficulty });
+        return;
+    }
+
+    let mut app = App::new();
+
+    app
+        .add_plugins(DefaultPlugins
             // Stops pixel art from being blurry.
-            ImagePlugin::default_nearest()
-        ))
-        .add_plugin(FrameTimeDiagnosticsPlugin)
-
-        .add_state::<AppState>()
-        
-        .insert_resource(game_ui::scoreboard::Scoreboard { score: 0 })
-        .insert_resource(game_ui::high_score::HighScore { highscore: 0 })
-        .insert_resource(game_ui::fps::FpsSpawned(false))
-        .insert_resource(game_ui::high_score::HighScoreSpawned(false))
-        .insert_resource(game_ui::high_score::HighScoreLoaded(false))
-        .insert_resource(ClearColor(BACKGROUND_COLOR))
-
-        .add_event::<bird::BirdCollisionEvent>()
-        .add_event::<bird::BirdJumpEvent>()
-        .add_event::<bird::BirdPointEvent>()
-
-        .add_startup_system(bird::setup)
-        .add_startup_system(pipes::setup)
-        .add_startup_system(camera::setup)
-        .add_startup_system(game_ui::setup)
-
-        .add_system(game_ui::menu::setup.in_schedule(OnEnter(AppState::MainMenu)))
-        .add_system(game_ui::menu::remove_menu_text.in_schedule(OnExit(AppState::MainMenu)))
-
-        .add_system(game_ui::fps::setup.in_schedule(OnEnter(AppState::MainMenu)))
-
-        .add_system(game_ui::high_score::setup.in_schedule(OnEnter(AppState::MainMenu))
-            .after(game_ui::menu::setup)
-        )
-
-        .add_system(game_ui::high_score::load_high_score.in_schedule(OnEnter(AppState::MainMenu)))
-        .add_system(game_ui::high_score::save_high_score.in_schedule(OnExit(AppState::InGame)))
-
-        .add_system(game_ui::scoreboard::setup.in_schedule(OnEnter(AppState::InGame)))
-        .add_system(game_ui::scoreboard::remove_scoreboard_text.in_schedule(OnExit(AppState::InGame)))
-
-        .add_systems(
-            (
-                bird::game_start,
-                bird::idle_bird_jump,
-            ).in_set(OnUpdate(AppState::MainMenu))
-        )
-        .add_systems(
-            (
-                bird::apply_bird_velocity,
-                bird::apply_bird_gravity,
-                bird::rotate_bird,
-
-                bird::bird_jump_sound,
-                bird::bird_point_sound,
-
-                pipes::apply_pipes_velocity,
-
-                game_ui::fps::update_fps,
-
-                game_ui::high_score::update_highscore_text,
-            )
-            .in_schedule(CoreSchedule::FixedUpdate),
+            .set(ImagePlugin::default_nearest())
+            // Picks up edits to the tuning file while the game runs.
+            .set(AssetPlugin {
+                watch_for_changes: true,
+                ..default()
+            })
         )
-        .add_systems(
-            (
-                bird::move_bird,
-                pipes::move_pipes,
-
-                bird::bird_pipe_collisions,
-                bird::bird_point_collisions,
-                bird::bird_boundary_collisions,
+        .add_plugin(FrameTimeDiagnosticsPlugin)
 
-                game_ui::scoreboard::update_scoreboard,
+        .add_plugin(SimulationPlugin { seed, replay, tuning, difficulty })
+        .add_plugin(PresentationPlugin);
 
-                game_ui::high_score::update_highscore,
+    #[cfg(feature = "debug")]
+    app.add_plugin(flappy_qube::debug::DebugPlugin);
 
-                game_over::game_over,
-            )
-            .in_set(OnUpdate(AppState::InGame))
-        )
-        .run();
+    app.run();
 }
 
-// Components & Resources used by more then one module outside main
-#[derive(Component, Deref, DerefMut, Debug)]
-pub struct Velocity(Vec2);
-
-#[derive(Component)]
-pub struct Collider;
-
-#[derive(Debug, Clone, Copy, Eq, Default, PartialEq, Hash, States)]
-pub enum AppState {
-    #[default]
-    MainMenu,
-    InGame,
+// Returns the value following a `--flag value` pair
+fn arg_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|arg| arg == flag)
+        .and_then(|index| args.get(index + 1))
+        .map(String::as_str)
 }
@@ -0,0 +1,459 @@
+//! Looping background music that follows the [`AppState`].
+//!
+//! Tracks are short note sheets (`*.track.ron`) rendered into samples when
+//! they load, so they can be written and tweaked without an audio editor.
+//! Changing track crossfades between the two, and the music ducks while
+//! the game over sting plays.
+
+use std::{sync::Arc, time::Duration};
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    audio::Source,
+    prelude::*,
+    reflect::TypeUuid,
+    utils::{BoxedFuture, HashMap},
+};
+use serde::Deserialize;
+
+use super::{
+    AppState,
+    audio::{AudioSettings, PlaySound, SoundCue},
+};
+
+// Constants
+const SAMPLE_RATE: u32 = 22050;
+
+const CROSSFADE_SECONDS: f32 = 1.5;
+
+// Roughly the length of the game over sting.
+const DUCK_SECONDS: f32 = 2.;
+const DUCK_VOLUME: f32 = 0.25;
+const DUCK_FADE_SECONDS: f32 = 0.3;
+
+const LEAD_VOLUME: f32 = 0.12;
+const BASS_VOLUME: f32 = 0.2;
+
+/// A track as written in its `.track.ron` file. Both voices are lists of
+/// eighth notes like `"C4"` or `"F#3"`, `"-"` is a rest.
+#[derive(Deserialize, Debug)]
+struct TrackSheet {
+    bpm: f32,
+    lead: Vec<String>,
+    bass: Vec<String>,
+}
+
+/// A rendered track, plays on a loop forever.
+#[derive(TypeUuid, Debug, Clone)]
+#[uuid = "b1e4f2a0-6c9d-4e3b-8f7a-1d2c3b4a5e6f"]
+pub struct MusicTrack {
+    samples: Arc<[f32]>,
+}
+
+impl MusicTrack {
+    fn render(sheet: &TrackSheet) -> Result<Self, bevy::asset::Error> {
+        if !sheet.bpm.is_finite() || sheet.bpm <= 0. {
+            return Err(bevy::asset::Error::msg(format!("invalid bpm {}", sheet.bpm)));
+        }
+
+        let step_length = (SAMPLE_RATE as f32 * 30. / sheet.bpm) as usize;
+        let steps = sheet.lead.len().max(sheet.bass.len());
+
+        if steps == 0 || step_length == 0 {
+            return Err(bevy::asset::Error::msg("track has no notes"));
+        }
+
+        let mut samples = vec![0.; steps * step_length];
+
+        for (notes, voice) in [(&sheet.lead, Voice::Lead), (&sheet.bass, Voice::Bass)] {
+            for (step, note) in notes.iter().enumerate() {
+                let Some(frequency) = note_frequency(note)? else {
+                    continue;
+                };
+
+                let start = step * step_length;
+                for (index, sample) in samples[start..start + step_length].iter_mut().enumerate() {
+                    *sample += voice.sample(frequency, index, step_length);
+                }
+            }
+        }
+
+        Ok(MusicTrack { samples: samples.into() })
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Voice {
+    Lead,
+    Bass,
+}
+
+impl Voice {
+    // One sample of a note `index` samples in
+    fn sample(&self, frequency: f32, index: usize, note_length: usize) -> f32 {
+        let seconds = index as f32 / SAMPLE_RATE as f32;
+        let phase = (seconds * frequency).fract();
+
+        // A quick fade in and out so notes don't click.
+        let edge = (SAMPLE_RATE / 200) as f32;
+        let envelope = (index as f32 / edge)
+            .min((note_length - index) as f32 / edge)
+            .min(1.);
+
+        match self {
+            // Square wave that decays over the note.
+            Voice::Lead => {
+                let decay = 1. - 0.5 * index as f32 / note_length as f32;
+                let square = if phase < 0.5 { 1. } else { -1. };
+
+                square * decay * envelope * LEAD_VOLUME
+            }
+            // Triangle wave.
+            Voice::Bass => {
+                let triangle = 4. * (phase - 0.5).abs() - 1.;
+
+                triangle * envelope * BASS_VOLUME
+            }
+        }
+    }
+}
+
+// The frequency of a note like `A4` or `C#3`, none for a rest
+fn note_frequency(note: &str) -> Result<Option<f32>, bevy::asset::Error> {
+    if note == "-" {
+        return Ok(None);
+    }
+
+    let invalid = || bevy::asset::Error::msg(format!("invalid note `{note}`"));
+
+    let mut chars = note.chars();
+    let semitone = match chars.next().ok_or_else(invalid)? {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return Err(invalid()),
+    };
+
+    let rest = chars.as_str();
+    let (semitone, octave) = match rest.strip_prefix('#') {
+        Some(octave) => (semitone + 1, octave),
+        None => (semitone, rest),
+    };
+    let octave: i32 = octave.parse().map_err(|_| invalid())?;
+
+    let midi = (octave + 1) * 12 + semitone;
+
+    Ok(Some(440. * 2f32.powf((midi - 69) as f32 / 12.)))
+}
+
+pub struct TrackDecoder {
+    samples: Arc<[f32]>,
+    position: usize,
+}
+
+impl Iterator for TrackDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let sample = self.samples[self.position];
+        self.position = (self.position + 1) % self.samples.len();
+
+        Some(sample)
+    }
+}
+
+impl Source for TrackDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+impl Decodable for MusicTrack {
+    type DecoderItem = f32;
+    type Decoder = TrackDecoder;
+
+    fn decoder(&self) -> Self::Decoder {
+        TrackDecoder {
+            samples: self.samples.clone(),
+            position: 0,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct TrackLoader;
+
+impl AssetLoader for TrackLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let sheet: TrackSheet = ron::de::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(MusicTrack::render(&sheet)?));
+
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["track.ron"]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MusicCue {
+    Menu,
+    Game,
+}
+
+impl MusicCue {
+    pub const ALL: [MusicCue; 2] = [
+        MusicCue::Menu,
+        MusicCue::Game,
+    ];
+
+    pub fn path(&self) -> &'static str {
+        match self {
+            MusicCue::Menu => "music/menu.track.ron",
+            MusicCue::Game => "music/game.track.ron",
+        }
+    }
+
+    fn for_state(state: AppState) -> Self {
+        match state {
+            AppState::InGame => MusicCue::Game,
+            _ => MusicCue::Menu,
+        }
+    }
+}
+
+// Components, Resources, Events
+
+/// Every music track, loaded at startup.
+#[derive(Resource, Default)]
+pub struct MusicHandles(HashMap<MusicCue, Handle<MusicTrack>>);
+
+impl MusicHandles {
+    pub fn get(&self, cue: MusicCue) -> Handle<MusicTrack> {
+        self.0
+            .get(&cue)
+            .expect("Should be loaded in music::setup")
+            .clone()
+    }
+}
+
+struct PlayingTrack {
+    cue: MusicCue,
+    sink: Handle<AudioSink>,
+    // From 0 when silent to 1 when fully faded in.
+    fade: f32,
+}
+
+/// The tracks playing, the last one is fading in and the rest fading out.
+#[derive(Resource)]
+pub struct MusicPlayer {
+    tracks: Vec<PlayingTrack>,
+    duck: Timer,
+    duck_volume: f32,
+}
+
+impl Default for MusicPlayer {
+    fn default() -> Self {
+        let mut duck = Timer::from_seconds(DUCK_SECONDS, TimerMode::Once);
+        duck.tick(duck.duration());
+
+        MusicPlayer {
+            tracks: Vec::new(),
+            duck,
+            duck_volume: 1.,
+        }
+    }
+}
+
+// Initial Setup
+pub fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+) {
+    let handles = MusicCue::ALL
+        .into_iter()
+        .map(|cue| (cue, asset_server.load(cue.path())))
+        .collect();
+
+    commands.insert_resource(MusicHandles(handles));
+}
+
+// Starts the track for the current state, crossfades from the last one
+// and ducks under the game over sting
+pub fn play_music(
+    time: Res<Time>,
+    state: Res<State<AppState>>,
+    music_handles: Res<MusicHandles>,
+    audio_settings: Res<AudioSettings>,
+    audio: Res<Audio<MusicTrack>>,
+    audio_sinks: Res<Assets<AudioSink>>,
+    mut play_sound_events: EventReader<PlaySound>,
+    mut music_player: ResMut<MusicPlayer>,
+) {
+    let wanted = MusicCue::for_state(state.0);
+
+    if music_player.tracks.last().map(|track| track.cue) != Some(wanted) {
+        let sink = audio.play_with_settings(
+            music_handles.get(wanted),
+            PlaybackSettings::ONCE.with_volume(0.),
+        );
+
+        music_player.tracks.push(PlayingTrack {
+            cue: wanted,
+            sink: audio_sinks.get_handle(sink),
+            fade: 0.,
+        });
+    }
+
+    if play_sound_events.iter().any(|PlaySound(cue)| *cue == SoundCue::GameOver) {
+        music_player.duck.reset();
+    }
+
+    let delta = time.delta_seconds();
+    music_player.duck.tick(time.delta());
+
+    let duck_target = if music_player.duck.finished() { 1. } else { DUCK_VOLUME };
+    let duck_step = delta / DUCK_FADE_SECONDS;
+    music_player.duck_volume += (duck_target - music_player.duck_volume).clamp(-duck_step, duck_step);
+
+    let volume = audio_settings.music_volume() * music_player.duck_volume;
+    let fade_step = delta / CROSSFADE_SECONDS;
+    let newest = music_player.tracks.len() - 1;
+
+    for (index, track) in music_player.tracks.iter_mut().enumerate() {
+        track.fade = if index == newest {
+            (track.fade + fade_step).min(1.)
+        } else {
+            (track.fade - fade_step).max(0.)
+        };
+
+        if let Some(sink) = audio_sinks.get(&track.sink) {
+            sink.set_volume(track.fade * volume);
+
+            // Sinks keep playing after their handle is dropped.
+            if index != newest && track.fade <= 0. {
+                sink.stop();
+            }
+        }
+    }
+
+    // Tracks that haven't started playing yet are kept until they have
+    // been stopped.
+    let mut index = 0;
+    music_player.tracks.retain(|track| {
+        let keep = index == newest || track.fade > 0. || audio_sinks.get(&track.sink).is_none();
+        index += 1;
+        keep
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frequency(note: &str) -> f32 {
+        note_frequency(note)
+            .expect("Should be a valid note")
+            .expect("Should be a note, not a rest")
+    }
+
+    fn sheet(lead: &[&str], bass: &[&str]) -> TrackSheet {
+        TrackSheet {
+            bpm: 120.,
+            lead: lead.iter().map(|note| note.to_string()).collect(),
+            bass: bass.iter().map(|note| note.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn notes_are_tuned_to_a4() {
+        assert_eq!(frequency("A4"), 440.);
+        assert_eq!(frequency("A5"), 880.);
+        assert!((frequency("C4") - 261.63).abs() < 0.01);
+        assert!((frequency("G2") - 98.).abs() < 0.01);
+    }
+
+    #[test]
+    fn sharps_are_a_semitone_up() {
+        assert!((frequency("A#4") - 466.16).abs() < 0.01);
+        assert_eq!(frequency("E#4"), frequency("F4"));
+        assert_eq!(frequency("B#3"), frequency("C4"));
+    }
+
+    #[test]
+    fn dash_is_a_rest() {
+        assert_eq!(note_frequency("-").expect("Should be a rest"), None);
+    }
+
+    #[test]
+    fn bad_notes_are_refused() {
+        for note in ["", "H4", "c4", "C", "C#", "Cb4", "C##4", "C4.5", "--"] {
+            assert!(note_frequency(note).is_err(), "{note:?}");
+        }
+    }
+
+    #[test]
+    fn rendered_track_is_one_step_per_note() {
+        let track = MusicTrack::render(&sheet(&["C5", "-", "E5"], &["C3"]))
+            .expect("Should render");
+
+        // An eighth note at 120 bpm is a quarter of a second.
+        let step_length = SAMPLE_RATE as usize / 4;
+        assert_eq!(track.samples.len(), 3 * step_length);
+
+        let step = |index: usize| &track.samples[index * step_length..(index + 1) * step_length];
+        assert!(step(0).iter().any(|sample| sample.abs() > 0.1));
+        assert!(step(1).iter().all(|sample| *sample == 0.));
+        assert!(step(2).iter().any(|sample| sample.abs() > 0.05));
+
+        // Notes fade in and out so they don't click.
+        assert!(track.samples[0].abs() < 0.01);
+        assert!(step(0)[step_length - 1].abs() < 0.01);
+        assert!(track.samples.iter().all(|sample| sample.abs() <= LEAD_VOLUME + BASS_VOLUME));
+    }
+
+    #[test]
+    fn broken_tracks_are_refused() {
+        assert!(MusicTrack::render(&sheet(&[], &[])).is_err());
+        assert!(MusicTrack::render(&sheet(&["C5", "X5"], &[])).is_err());
+        for bpm in [0., -60., f32::NAN] {
+            assert!(MusicTrack::render(&TrackSheet { bpm, ..sheet(&["C5"], &[]) }).is_err());
+        }
+    }
+
+    #[test]
+    fn shipped_tracks_render() {
+        for cue in MusicCue::ALL {
+            let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("assets")
+                .join(cue.path());
+            let text = std::fs::read_to_string(&path).expect("Should be readable");
+            let sheet: TrackSheet = ron::from_str(&text).expect("Should be a valid track");
+
+            assert!(MusicTrack::render(&sheet).is_ok(), "{}", path.display());
+        }
+    }
+}
@@ -1,148 +1,175 @@
 use bevy::prelude::*;
-use bevy::sprite::MaterialMesh2dBundle;
 
 use rand::prelude::*;
+use rand_chacha::ChaCha8Rng;
 
 use std::ops::RangeInclusive;
 
 use super::{
     Velocity, 
-    TIME_STEP,
     Collider,
+    AppState,
+    camera::VisibleArea,
+    game_ui::scoreboard::Scoreboard,
+    tuning::Tuning,
 };
 
 // Constants
-pub const PIPE_X_SIZE: f32 = 90.;//100
-pub const PIPE_Y_SIZE: f32 = 750.;//800
+pub const PIPE_TEXTURE_SIZE: Vec2 = Vec2::new(20., 160.);
+const PIPE_SCALE: Vec3 = Vec3::new(5., 5., 0.);
 
-// Always use this for the amount of pipes in game.
-pub const PIPE_AMOUNT: i32 = 6;
-pub const PIPE_HEIGHT_RANGE: RangeInclusive<i32> = 200..=700;
+/// Size of a pipe as drawn.
+pub const PIPE_X_SIZE: f32 = PIPE_TEXTURE_SIZE.x * PIPE_SCALE.x;
+pub const PIPE_Y_SIZE: f32 = PIPE_TEXTURE_SIZE.y * PIPE_SCALE.y;
 
-const PIPE_COLOR: Color = Color::rgb(0.1, 0.7, 0.2);
-const POINT_MARKER: Color = Color::rgba(0., 0., 0., 0.);
-const PIPE_GAP_X: f32 = 500.;
-const PIPE_GAP_Y: f32 = PIPE_Y_SIZE + 250.;
+pub const PIPE_SPRITE: &str = "sprites/Pipe.png";
 
 // Initial Setup
 pub fn setup(
     mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    asset_server: Res<AssetServer>,
+    course_rng: Res<CourseRng>,
+    tuning: Res<Tuning>,
 ) {
-    let pipe_handle = asset_server.load("sprites/Pipe.png");
-
-    // Spawns three entities per loop iteration. First is the top pipe,
-    // second is the bottom pipe and third is the point marker.
-    for i in 1..=PIPE_AMOUNT {
-        let pipe_height: f32 = thread_rng()
-            .gen_range(PIPE_HEIGHT_RANGE) as f32; 
-
-        // Top Pipes
-        commands.spawn((
-            PipeBundle {
-                sprite_bundle: SpriteBundle {
-                    texture: pipe_handle.clone(),
-                    transform: Transform {
-                        translation: Vec3::new(i as f32 * PIPE_GAP_X, pipe_height, 1.),
-                        scale: Vec3::new(5., 5., 0.),
-                        ..default()
-                    },
-                    ..default()
-                },
-
-                velocity: Velocity(Vec2::new(0., 0.)),
-                offset: Offset(0.),
-                collider: Collider, 
-                pipe: Pipe,
-                starting_position: StartingPosition(
-                    Vec3::new(i as f32 * PIPE_GAP_X, pipe_height, 1.),
-                )
-            },
-            NumberOf(i as usize),
-        ));
+    info!("Course seed: {}", course_rng.seed());
 
-        // Bottom Pipes
-        commands.spawn((
-            PipeBundle {
-                sprite_bundle: SpriteBundle {
-                    texture: pipe_handle.clone(),
-                    transform: Transform {
-                        translation: Vec3::new(i as f32 * PIPE_GAP_X, pipe_height - PIPE_GAP_Y, 1.),
-                        scale: Vec3::new(5., 5., 0.),
-                        rotation: Quat::from_rotation_x(f32::to_radians(180.)),
-                        ..default()
-                    },
-                    ..default()
-                },
-
-                velocity: Velocity(Vec2::new(0., 0.)),
-                offset: Offset(-PIPE_GAP_Y),
-                collider: Collider, 
-                pipe: Pipe,
-                starting_position: StartingPosition(
-                    Vec3::new(i as f32 * PIPE_GAP_X, pipe_height - PIPE_GAP_Y, 1.),
-                )
-            },
-            NumberOf(i as usize),
-        ));
+    commands.insert_resource(CourseLayout::from(&*tuning));
+    commands.insert_resource(CourseSpawner::default());
+}
+
+/// Spawns column `number` of the course at `x`. The top pipe, the bottom
+/// pipe and the point marker between them are separate entities.
+pub fn spawn_column(
+    commands: &mut Commands,
+    number: usize,
+    x: f32,
+    pipe_height: f32,
+    gap_y: f32,
+) {
+    // Distance between the middle of the top and bottom pipe.
+    let pipe_gap_y = PIPE_Y_SIZE + gap_y;
+
+    // Top Pipe
+    commands.spawn((
+        PipeBundle {
+            spatial_bundle: SpatialBundle::from_transform(Transform {
+                translation: Vec3::new(x, pipe_height, 1.),
+                scale: PIPE_SCALE,
+                ..default()
+            }),
+
+            velocity: Velocity(Vec2::new(0., 0.)),
+            collider: Collider::rect_from_texture(PIPE_TEXTURE_SIZE, PIPE_SCALE),
+            pipe: Pipe,
+        },
+        NumberOf(number),
+    ));
+
+    // Bottom Pipe
+    commands.spawn((
+        PipeBundle {
+            spatial_bundle: SpatialBundle::from_transform(Transform {
+                translation: Vec3::new(x, pipe_height - pipe_gap_y, 1.),
+                scale: PIPE_SCALE,
+                rotation: Quat::from_rotation_x(f32::to_radians(180.)),
+            }),
 
-        // Point Markers
-        commands.spawn((
-            PipePointBundle {
-                mesh_bundle: MaterialMesh2dBundle {
-                    mesh: meshes.add(shape::Box::new(1., 1., 1.).into()).into(),
-                    material: materials.add(ColorMaterial::from(POINT_MARKER)),
-                    transform: Transform {
-                        translation: Vec3::new(
-                            i as f32 * PIPE_GAP_X + PIPE_X_SIZE / 2.,
-                            pipe_height - PIPE_GAP_Y / 2.,
-                            1.
-                        ),
-                        // Increased scale of x to 10 from 1 to account for potential
-                        // collision skipping when lagging.
-                        scale: Vec3::new(10., PIPE_GAP_Y - PIPE_Y_SIZE, 0.),
-                        ..default()
-                    },
-                    ..default()
-                },
-
-                velocity: Velocity(Vec2::new(0., 0.)),
-                collider: Collider,
-                offset: Offset(-PIPE_GAP_Y / 2.),
-                point_marker: PointMarker,
-                been_added: BeenAdded(false),
-                starting_position: StartingPosition(
-                    Vec3::new(i as f32 * PIPE_GAP_X, pipe_height - PIPE_GAP_Y / 2., 1.),
-                )
+            velocity: Velocity(Vec2::new(0., 0.)),
+            collider: Collider::rect_from_texture(PIPE_TEXTURE_SIZE, PIPE_SCALE),
+            pipe: Pipe,
+        },
+        NumberOf(number),
+        Bottom,
+    ));
+
+    // Point Marker
+    commands.spawn((
+        PipePointBundle {
+            // Point markers are never drawn, a thin line across the gap
+            // at the back of the pipes.
+            spatial_bundle: SpatialBundle::from_transform(Transform::from_xyz(
+                x + PIPE_X_SIZE / 2.,
+                pipe_height - pipe_gap_y / 2.,
+                1.,
+            )),
+
+            velocity: Velocity(Vec2::new(0., 0.)),
+            collider: Collider::Rect {
+                size: Vec2::new(1., gap_y),
             },
-            NumberOf(i as usize),
-        ));
+            point_marker: PointMarker,
+            been_added: BeenAdded(false),
+        },
+        NumberOf(number),
+    ));
+}
+
+// Starts the course over when the tuning changes how it is laid out.
+// Waits for the current run to end so pipes don't jump around mid run.
+pub fn rebuild_course(
+    mut commands: Commands,
+    pipes_query: Query<Entity, With<NumberOf>>,
+    mut course_rng: ResMut<CourseRng>,
+    mut spawner: ResMut<CourseSpawner>,
+    mut layout: ResMut<CourseLayout>,
+    tuning: Res<Tuning>,
+    state: Res<State<AppState>>,
+) {
+    if state.0 == AppState::InGame || *layout == CourseLayout::from(&*tuning) {
+        return;
     }
 
+    *layout = CourseLayout::from(&*tuning);
+
+    // Starts the course over so a seed still gives the same course.
+    course_rng.rewind();
+    clear_course(&mut commands, &pipes_query, &mut spawner);
+}
+
+/// Despawns every column so the course starts again from its first one.
+pub fn clear_course(
+    commands: &mut Commands,
+    pipes_query: &Query<Entity, With<NumberOf>>,
+    spawner: &mut CourseSpawner,
+) {
+    for pipe in pipes_query {
+        commands.entity(pipe).despawn();
+    }
+
+    *spawner = CourseSpawner::default();
+}
+
+// Gives pipes their sprite once they have been spawned by setup
+pub fn add_sprites(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    query: Query<Entity, Added<Pipe>>,
+) {
+    let pipe_handle: Handle<Image> = asset_server.load(PIPE_SPRITE);
+
+    for pipe in &query {
+        commands.entity(pipe).insert((
+            Sprite::default(),
+            pipe_handle.clone(),
+        ));
+    }
 }
 
 // Components, Resources, Events
 #[derive(Bundle)]
 struct PipeBundle {
-    sprite_bundle: SpriteBundle,
+    spatial_bundle: SpatialBundle,
     velocity: Velocity,
     collider: Collider,
-    offset: Offset,
     pipe: Pipe,
-    starting_position: StartingPosition,
 }
 
 #[derive(Bundle)]
 struct PipePointBundle {
-    mesh_bundle: MaterialMesh2dBundle<ColorMaterial>,
+    spatial_bundle: SpatialBundle,
     velocity: Velocity,
     collider: Collider,
-    offset: Offset,
     point_marker: PointMarker,
     been_added: BeenAdded,
-    starting_position: StartingPosition,
 }
 
 #[derive(Component)]
@@ -151,51 +178,194 @@ pub struct Pipe;
 #[derive(Component)]
 pub struct PointMarker;
 
-#[derive(Component, Debug)]
-pub struct Offset(pub f32);
+/// The pipe under the gap.
+#[derive(Component)]
+pub struct Bottom;
 
 #[derive(Component, Debug)]
 pub struct BeenAdded(pub bool);
 
-#[derive(Component)]
-pub struct StartingPosition(pub Vec3);
-
+/// Which column of the course a pipe or point marker is part of,
+/// counting from 1. Columns spawned from the debug console are 0.
 #[derive(Component, Debug)]
 pub struct NumberOf(pub usize);
 
-// Pipe Movement: Add a constant value to pipes velocity.
-// Also moves the pipes to the right edge of the screen as
-// they move off the left side.
+/// The tuning values the pipes on screen were spawned with.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct CourseLayout {
+    pub gap_x: f32,
+    pub gap_y: f32,
+}
+
+impl From<&Tuning> for CourseLayout {
+    fn from(tuning: &Tuning) -> Self {
+        CourseLayout {
+            gap_x: tuning.pipe_gap_x,
+            gap_y: tuning.pipe_gap_y,
+        }
+    }
+}
+
+/// Where the course is up to. Column `n` starts `n` gaps to the right of
+/// the bird and scrolls left with the pipes.
+#[derive(Resource, Debug)]
+pub struct CourseSpawner {
+    /// The next column to spawn.
+    pub next_column: usize,
+    /// How far the course has scrolled since the run started.
+    pub scrolled: f32,
+}
+
+impl Default for CourseSpawner {
+    fn default() -> Self {
+        CourseSpawner {
+            next_column: 1,
+            scrolled: 0.,
+        }
+    }
+}
+
+impl CourseSpawner {
+    fn column_x(&self, column: usize, gap_x: f32) -> f32 {
+        column as f32 * gap_x + self.scrolled
+    }
+}
+
+/// Random number generator for the course. The same seed always gives
+/// the same pipe heights, in the same order.
+#[derive(Resource)]
+pub struct CourseRng {
+    seed: u64,
+    // A fixed seed is reused for every run, otherwise each run seeds
+    // the next one.
+    fixed: bool,
+    rng: ChaCha8Rng,
+    last_height: Option<f32>,
+}
+
+impl CourseRng {
+    /// Every run plays the course of `seed`.
+    pub fn fixed(seed: u64) -> Self {
+        CourseRng {
+            seed,
+            fixed: true,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            last_height: None,
+        }
+    }
+
+    /// Starts from a random seed and plays a different course every run.
+    pub fn random() -> Self {
+        CourseRng {
+            fixed: false,
+            ..CourseRng::fixed(rand::random())
+        }
+    }
+
+    /// The seed of the current run.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// A height in `range` at most `max_jump` away from the height rolled
+    /// before it.
+    pub fn pipe_height(&mut self, range: &RangeInclusive<i32>, max_jump: f32) -> f32 {
+        let (mut low, mut high) = (*range.start(), *range.end());
+
+        if let Some(last_height) = self.last_height {
+            low = low.max((last_height - max_jump).ceil() as i32);
+            high = high.min((last_height + max_jump).floor() as i32);
+        }
+
+        // The last height can be outside of the range when the tuning
+        // changed since it was rolled.
+        let height = if low <= high {
+            self.rng.gen_range(low..=high)
+        } else {
+            self.rng.gen_range(range.clone())
+        } as f32;
+
+        self.last_height = Some(height);
+        height
+    }
+
+    /// Rewinds to the start of a course for a new run.
+    pub fn next_run(&mut self) {
+        if !self.fixed {
+            self.seed = self.rng.gen();
+        }
+
+        self.rewind();
+    }
+
+    /// Goes back to the start of the current course.
+    pub fn rewind(&mut self) {
+        self.rng = ChaCha8Rng::seed_from_u64(self.seed);
+        self.last_height = None;
+    }
+}
+
+// Pipe Movement: Sets the pipes velocity for the current score
 pub fn move_pipes(
-    mut query_pipes: Query<(
-        &mut Transform, 
-        &mut Velocity, 
-        &Offset, 
-        Option<&PointMarker>, 
-        Option<&mut BeenAdded>,
-    ), 
-        With<Collider>
-    >,
+    mut query_pipes: Query<&mut Velocity, With<NumberOf>>,
+    mut spawner: ResMut<CourseSpawner>,
+    tuning: Res<Tuning>,
+    scoreboard: Res<Scoreboard>,
+) {
+    let speed = tuning.course_at(scoreboard.score).pipe_speed;
+
+    for mut velocity in &mut query_pipes {
+        velocity.x = speed;
+    }
+
+    // Moves with the pipes so new columns line up with the old ones.
+    spawner.scrolled += speed * tuning.time_step;
+}
+
+// Spawns the columns about to scroll into view just past the right edge
+// of the screen. Each column's height and gap are picked for the score
+// the bird will have when it gets there, so the course doesn't depend on
+// how wide the screen is.
+pub fn spawn_pipes(
+    mut commands: Commands,
+    mut spawner: ResMut<CourseSpawner>,
+    mut course_rng: ResMut<CourseRng>,
+    visible_area: Res<VisibleArea>,
+    layout: Res<CourseLayout>,
+    tuning: Res<Tuning>,
 ) {
-    let pipe_height = thread_rng()
-        .gen_range(PIPE_HEIGHT_RANGE) as f32;
+    // The layout only changes between runs, so columns of the same run
+    // are always the same distance apart.
+    assert!(layout.gap_x > 0., "Columns should be a positive distance apart");
 
-    for (mut transform, mut velocity, offset, point_marker, been_added) in &mut query_pipes {
-        velocity.x = -300. * TIME_STEP;
+    loop {
+        let column = spawner.next_column;
+        let x = spawner.column_x(column, layout.gap_x);
 
-        let x_pos = transform.translation.x;
+        // A pipe width of margin is more than a tick of movement, so
+        // columns are never spawned already on screen.
+        if x - PIPE_X_SIZE / 2. > visible_area.right + PIPE_X_SIZE {
+            break;
+        }
 
-        if x_pos <= -1000.  {
-            transform.translation.x = 2000.;
-            transform.translation.y = pipe_height + offset.0;
-        } else if x_pos <= -1000. + PIPE_X_SIZE / 2. && point_marker.is_some() {
-            transform.translation.x = 2000. + PIPE_X_SIZE / 2.;
-            transform.translation.y = pipe_height + offset.0;
+        // Every earlier column is passed by then.
+        let course = tuning.course_at(column as i128 - 1);
+        let pipe_height = course_rng.pipe_height(&tuning.pipe_height_range, course.pipe_height_jump);
 
-            let mut been_added = been_added
-                .expect("Should be Seme<T>");
+        spawn_column(&mut commands, column, x, pipe_height, course.pipe_gap_y);
+        spawner.next_column += 1;
+    }
+}
 
-            been_added.0 = false;
+// Despawns pipes once they have scrolled past the left edge of the screen
+pub fn despawn_pipes(
+    mut commands: Commands,
+    query: Query<(Entity, &Transform), With<NumberOf>>,
+    visible_area: Res<VisibleArea>,
+) {
+    for (entity, transform) in &query {
+        if transform.translation.x + PIPE_X_SIZE < visible_area.left {
+            commands.entity(entity).despawn();
         }
     }
 }
@@ -203,10 +373,79 @@ pub fn move_pipes(
 // Apply the velocity's calculated in other systems to the transforms
 // of the game entities
 pub fn apply_pipes_velocity(
-    mut query: Query<(&mut Transform, &Velocity), With<Collider>>
+    mut query: Query<(&mut Transform, &Velocity), With<NumberOf>>,
+    tuning: Res<Tuning>,
 ) {
     for (mut transform, velocity) in &mut query {
-        transform.translation.x += velocity.x;
-        transform.translation.y += velocity.y;
+        transform.translation.x += velocity.x * tuning.time_step;
+        transform.translation.y += velocity.y * tuning.time_step;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEIGHT_RANGE: RangeInclusive<i32> = 200..=700;
+    const MAX_JUMP: f32 = 150.;
+
+    fn heights(course_rng: &mut CourseRng) -> Vec<f32> {
+        (0..50)
+            .map(|_| course_rng.pipe_height(&HEIGHT_RANGE, MAX_JUMP))
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_gives_same_course() {
+        let course = heights(&mut CourseRng::fixed(42));
+
+        assert_eq!(heights(&mut CourseRng::fixed(42)), course);
+        assert_ne!(heights(&mut CourseRng::fixed(43)), course);
+        assert!(course.windows(2).all(|pair| (pair[0] - pair[1]).abs() <= MAX_JUMP));
+    }
+
+    #[test]
+    fn rewind_replays_the_course() {
+        for mut course_rng in [CourseRng::fixed(42), CourseRng::random()] {
+            let seed = course_rng.seed();
+            let course = heights(&mut course_rng);
+
+            course_rng.rewind();
+
+            assert_eq!(course_rng.seed(), seed);
+            assert_eq!(heights(&mut course_rng), course);
+        }
+    }
+
+    #[test]
+    fn next_run_keeps_a_fixed_seed() {
+        let mut course_rng = CourseRng::fixed(42);
+        let course = heights(&mut course_rng);
+
+        course_rng.next_run();
+
+        assert_eq!(course_rng.seed(), 42);
+        assert_eq!(heights(&mut course_rng), course);
+    }
+
+    #[test]
+    fn next_run_changes_a_random_seed() {
+        let mut course_rng = CourseRng::random();
+        let seed = course_rng.seed();
+        let course = heights(&mut course_rng);
+
+        course_rng.next_run();
+
+        assert_ne!(course_rng.seed(), seed);
+        assert_ne!(heights(&mut course_rng), course);
+
+        // The next seed only depends on the one before it.
+        let mut same_start = CourseRng {
+            fixed: false,
+            ..CourseRng::fixed(seed)
+        };
+        heights(&mut same_start);
+        same_start.next_run();
+        assert_eq!(same_start.seed(), course_rng.seed());
     }
 }
@@ -0,0 +1,385 @@
+//! Recording and playback of runs.
+//!
+//! A run is fully decided by the course seed, the difficulty, the tuning,
+//! where the bird was when the run started and the ticks the player
+//! flapped on, so that is all a replay stores. Playing one back feeds
+//! the flaps into [`FlapInput`] on the same ticks, which goes through the
+//! same `move_bird` path as the player's own input.
+
+use std::{
+    fmt,
+    fs,
+    io,
+    path::Path,
+    str::FromStr,
+};
+
+use bevy::prelude::*;
+
+use super::{
+    AppState,
+    Velocity,
+    bird::Bird,
+    bird::FlapInput,
+    difficulty::Difficulty,
+    game_over::UnrankedRun,
+    game_ui::scoreboard::Scoreboard,
+    pipes::CourseRng,
+    tuning::{BaseTuning, Tuning},
+};
+
+// Constants
+const REPLAY_HEADER: &str = "flappy-qube replay";
+const REPLAY_VERSION: u32 = 3;
+
+// Version 1 stored the start velocity per tick, at 60 ticks a second.
+const V1_TICKS_PER_SECOND: f32 = 60.;
+
+pub const LAST_REPLAY_PATH: &str = "replays/last.replay";
+
+/// Everything needed to play a run again.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Replay {
+    pub seed: u64,
+    pub difficulty: Difficulty,
+    /// The tuning file the run was played with, before the difficulty
+    /// was applied. Replays from before version 3 don't have it.
+    pub tuning: Option<Tuning>,
+    pub start_height: f32,
+    pub start_velocity: f32,
+    /// Ticks since the start of the run the player flapped on, in order.
+    pub flaps: Vec<u64>,
+    /// Score the run ended with.
+    pub score: i128,
+}
+
+impl Replay {
+    pub fn new(
+        seed: u64,
+        difficulty: Difficulty,
+        tuning: Tuning,
+        start_height: f32,
+        start_velocity: f32,
+    ) -> Self {
+        Replay {
+            seed,
+            difficulty,
+            tuning: Some(tuning),
+            start_height,
+            start_velocity,
+            flaps: Vec::new(),
+            score: 0,
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ReplayError> {
+        fs::read_to_string(path)?.parse()
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        if let Some(parent) = path.as_ref().parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(path, self.to_string())
+    }
+}
+
+// Replays are a few short lines of text so they can be pasted straight
+// into a bug report.
+impl fmt::Display for Replay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{REPLAY_HEADER} {REPLAY_VERSION}")?;
+        writeln!(f, "seed {}", self.seed)?;
+        writeln!(f, "difficulty {}", self.difficulty)?;
+        if let Some(tuning) = &self.tuning {
+            let tuning = ron::to_string(tuning).map_err(|_| fmt::Error)?;
+            writeln!(f, "tuning {tuning}")?;
+        }
+        writeln!(f, "start {} {}", self.start_height, self.start_velocity)?;
+        writeln!(f, "score {}", self.score)?;
+
+        write!(f, "flaps")?;
+        for flap in &self.flaps {
+            write!(f, " {flap}")?;
+        }
+        writeln!(f)
+    }
+}
+
+impl FromStr for Replay {
+    type Err = ReplayError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut lines = text.lines();
+
+        let version = lines
+            .next()
+            .and_then(|line| line.strip_prefix(REPLAY_HEADER))
+            .ok_or(ReplayError::NotAReplay)?;
+        let version: u32 = parse_value(version.trim())?;
+        if !(1..=REPLAY_VERSION).contains(&version) {
+            return Err(ReplayError::UnsupportedVersion(version));
+        }
+
+        let mut seed = None;
+        // Replays from before difficulties were all on Normal.
+        let mut difficulty = Difficulty::Normal;
+        let mut tuning = None;
+        let mut start = None;
+        let mut score = None;
+        let mut flaps = None;
+
+        for line in lines {
+            let mut values = line.split_whitespace();
+
+            match values.next() {
+                Some("seed") => seed = Some(parse_value(values.next().unwrap_or_default())?),
+                Some("difficulty") => difficulty = parse_value(values.next().unwrap_or_default())?,
+                // One line of RON, which can have spaces in it.
+                Some("tuning") => tuning = Some(parse_tuning(line.trim_start()["tuning".len()..].trim())?),
+                Some("start") => start = Some((
+                    parse_value(values.next().unwrap_or_default())?,
+                    parse_value(values.next().unwrap_or_default())?,
+                )),
+                Some("score") => score = Some(parse_value(values.next().unwrap_or_default())?),
+                Some("flaps") => flaps = Some(values
+                    .map(parse_value)
+                    .collect::<Result<Vec<u64>, _>>()?
+                ),
+                Some(key) => return Err(ReplayError::Invalid(format!("unknown key `{key}`"))),
+                None => (),
+            }
+        }
+
+        let (start_height, mut start_velocity) = start.ok_or(ReplayError::Missing("start"))?;
+        if version == 1 {
+            start_velocity *= V1_TICKS_PER_SECOND;
+        }
+
+        Ok(Replay {
+            seed: seed.ok_or(ReplayError::Missing("seed"))?,
+            difficulty,
+            tuning,
+            start_height,
+            start_velocity,
+            flaps: flaps.ok_or(ReplayError::Missing("flaps"))?,
+            score: score.ok_or(ReplayError::Missing("score"))?,
+        })
+    }
+}
+
+// Checked like the tuning file, a replay could hang the game otherwise.
+fn parse_tuning(value: &str) -> Result<Tuning, ReplayError> {
+    let tuning: Tuning = ron::from_str(value)
+        .map_err(|error| ReplayError::Invalid(format!("bad tuning: {error}")))?;
+
+    tuning
+        .validate()
+        .map_err(|error| ReplayError::Invalid(format!("bad tuning: {error}")))?;
+
+    Ok(tuning)
+}
+
+fn parse_value<T: FromStr>(value: &str) -> Result<T, ReplayError> {
+    value
+        .parse()
+        .map_err(|_| ReplayError::Invalid(format!("bad value `{value}`")))
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(io::Error),
+    NotAReplay,
+    UnsupportedVersion(u32),
+    Missing(&'static str),
+    Invalid(String),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Io(error) => write!(f, "couldn't read replay: {error}"),
+            ReplayError::NotAReplay => write!(f, "not a replay file"),
+            ReplayError::UnsupportedVersion(version) => write!(f, "unsupported replay version {version}"),
+            ReplayError::Missing(key) => write!(f, "replay has no `{key}`"),
+            ReplayError::Invalid(reason) => write!(f, "invalid replay: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<io::Error> for ReplayError {
+    fn from(error: io::Error) -> Self {
+        ReplayError::Io(error)
+    }
+}
+
+// Components, Resources, Events
+
+/// Fixed ticks since the current run started.
+#[derive(Resource, Default, Debug)]
+pub struct RunTick(pub u64);
+
+/// The replay of the current run, or of the last one once it is over.
+#[derive(Resource, Default)]
+pub struct ReplayRecorder {
+    pub replay: Replay,
+}
+
+/// Present while a replay is being played back.
+#[derive(Resource)]
+pub struct ReplayPlayback {
+    pub replay: Replay,
+    next_flap: usize,
+    /// The tuning file, put back once the replay is over.
+    pub file_tuning: Tuning,
+    // The seed to play after the replay, random when unset.
+    seed: Option<u64>,
+}
+
+impl ReplayPlayback {
+    pub fn new(replay: Replay, file_tuning: Tuning, seed: Option<u64>) -> Self {
+        ReplayPlayback {
+            replay,
+            next_flap: 0,
+            file_tuning,
+            seed,
+        }
+    }
+}
+
+// Starts playback without waiting for the player to start a run. With
+// assets to load it runs again once they are loaded.
+pub fn start_playback(
+    state: Res<State<AppState>>,
+    mut next_state: ResMut<NextState<AppState>>,
+    playback: Res<ReplayPlayback>,
+) {
+    if state.0 == AppState::Loading {
+        return;
+    }
+
+    if playback.replay.tuning.is_none() {
+        warn!("Replay was recorded without its tuning, it may not end the same with the current tuning");
+    }
+
+    next_state.set(AppState::InGame);
+}
+
+// Puts the bird where the replay started and begins recording the run
+pub fn begin_run(
+    mut bird_query: Query<(&mut Transform, &mut Velocity), With<Bird>>,
+    mut run_tick: ResMut<RunTick>,
+    mut recorder: ResMut<ReplayRecorder>,
+    playback: Option<Res<ReplayPlayback>>,
+    course_rng: Res<CourseRng>,
+    difficulty: Res<Difficulty>,
+    base_tuning: Res<BaseTuning>,
+) {
+    let (mut transform, mut velocity) = bird_query.single_mut();
+
+    if let Some(playback) = playback {
+        transform.translation.y = playback.replay.start_height;
+        velocity.y = playback.replay.start_velocity;
+    }
+
+    run_tick.0 = 0;
+    recorder.replay = Replay::new(
+        course_rng.seed(),
+        *difficulty,
+        base_tuning.0.clone(),
+        transform.translation.y,
+        velocity.y,
+    );
+}
+
+// Flaps on the ticks stored in the replay. Overwrites the flap input
+// so the player can't change a replay while it plays.
+pub fn play_flaps(
+    playback: Option<ResMut<ReplayPlayback>>,
+    run_tick: Res<RunTick>,
+    mut flap_input: ResMut<FlapInput>,
+) {
+    let Some(mut playback) = playback else {
+        return;
+    };
+
+    let next_flap = playback.next_flap;
+    let flaps = playback.replay.flaps.get(next_flap) == Some(&run_tick.0);
+    flap_input.0 = u32::from(flaps);
+
+    if flaps {
+        playback.next_flap += 1;
+    }
+}
+
+pub fn record_flaps(
+    flap_input: Res<FlapInput>,
+    run_tick: Res<RunTick>,
+    mut recorder: ResMut<ReplayRecorder>,
+) {
+    if flap_input.is_waiting() {
+        recorder.replay.flaps.push(run_tick.0);
+    }
+}
+
+// Runs before game_over resets the score
+pub fn record_score(
+    scoreboard: Res<Scoreboard>,
+    mut recorder: ResMut<ReplayRecorder>,
+) {
+    recorder.replay.score = scoreboard.score;
+}
+
+pub fn advance_run_tick(
+    mut run_tick: ResMut<RunTick>,
+) {
+    run_tick.0 += 1;
+}
+
+// Stops playback once the replayed run is over and goes back to the
+// tuning and course the player plays with
+pub fn end_playback(
+    mut commands: Commands,
+    playback: Option<Res<ReplayPlayback>>,
+    recorder: Res<ReplayRecorder>,
+    mut base_tuning: ResMut<BaseTuning>,
+) {
+    let Some(playback) = playback else {
+        return;
+    };
+
+    info!(
+        "Replay finished with a score of {} (recorded {})",
+        recorder.replay.score,
+        playback.replay.score,
+    );
+
+    base_tuning.0 = playback.file_tuning.clone();
+
+    // Inserted with commands so it replaces the course moved on by
+    // `reset_run`.
+    let course_rng = playback.seed.map_or_else(CourseRng::random, CourseRng::fixed);
+    commands.insert_resource(course_rng);
+
+    commands.remove_resource::<ReplayPlayback>();
+}
+
+// Keeps the last run the player played so it can be shared
+pub fn save_replay(
+    recorder: Res<ReplayRecorder>,
+    playback: Option<Res<ReplayPlayback>>,
+    unranked: Option<Res<UnrankedRun>>,
+) {
+    // Playing a replay back shouldn't replace the last real run, and a
+    // changed run wouldn't play back the same.
+    if playback.is_some() || unranked.is_some() {
+        return;
+    }
+
+    if let Err(error) = recorder.replay.save(LAST_REPLAY_PATH) {
+        warn!("Couldn't save replay to {LAST_REPLAY_PATH}: {error}");
+    }
+}
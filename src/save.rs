@@ -0,0 +1,367 @@
+//! Versioned save data stored in the platform data directory.
+//!
+//! Saves are written as RON to a temporary file which is then renamed over
+//! the old save, so a crash mid write never leaves a half written save.
+//! A save that can't be read is moved aside and replaced with defaults
+//! instead of stopping the game.
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use directories::ProjectDirs;
+use serde::{Serialize, Deserialize};
+
+use super::audio::AudioSettings;
+use super::difficulty::Difficulty;
+use super::input::{Binding, InputBindings};
+use super::leaderboard::{
+    Leaderboard,
+    LeaderboardEntry,
+    DEFAULT_MODE,
+    UNKNOWN_NAME,
+};
+use super::stats::Stats;
+
+// Constants
+pub const SAVE_VERSION: u32 = 8;
+
+const SAVE_FILE_NAME: &str = "save.ron";
+
+// Where the high score was kept before there was a save file.
+const LEGACY_HIGH_SCORE_PATH: &str = "highscore.txt";
+
+/// Everything the game keeps between sessions.
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct SaveData {
+    pub version: u32,
+    /// The only high score before version 4, only read to migrate it.
+    #[serde(skip_serializing)]
+    high_score: i128,
+    /// Added in version 4.
+    pub high_scores: BTreeMap<Difficulty, i128>,
+    pub leaderboard: Leaderboard,
+    /// Added in version 3.
+    pub stats: Stats,
+    /// The difficulty last picked in the menu. Added in version 4.
+    pub difficulty: Difficulty,
+    /// Added in version 5.
+    pub bindings: InputBindings,
+    /// Added in version 8.
+    pub audio: AudioSettings,
+}
+
+impl Default for SaveData {
+    fn default() -> Self {
+        SaveData {
+            version: SAVE_VERSION,
+            high_score: 0,
+            high_scores: BTreeMap::new(),
+            leaderboard: Leaderboard::default(),
+            stats: Stats::default(),
+            difficulty: Difficulty::default(),
+            bindings: InputBindings::default(),
+            audio: AudioSettings::default(),
+        }
+    }
+}
+
+impl SaveData {
+    pub fn high_score(&self, difficulty: Difficulty) -> i128 {
+        self.high_scores
+            .get(&difficulty)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    // Brings a save written by an older version of the game up to date.
+    // Fields added since are filled in by serde defaults, only changes to
+    // existing fields need a step here.
+    fn migrate(mut self) -> Self {
+        if self.version < SAVE_VERSION {
+            info!("Migrating save from version {} to {SAVE_VERSION}", self.version);
+        }
+
+        // Version 2 added the leaderboard. The old high score becomes its
+        // first entry, nobody knows who set it.
+        if self.version < 2 && self.leaderboard.is_empty() {
+            self.leaderboard.insert(LeaderboardEntry {
+                name: UNKNOWN_NAME.to_string(),
+                score: self.high_score,
+                date: None,
+                seed: None,
+                mode: DEFAULT_MODE.to_string(),
+            });
+        }
+
+        // Version 4 split the high score by difficulty. Everything before
+        // was played on Normal.
+        if self.version < 4 && self.high_score > 0 {
+            self.high_scores
+                .entry(Difficulty::Normal)
+                .or_insert(self.high_score);
+        }
+
+        // Version 6 added gamepads. Saved bindings keep what the player
+        // picked and gain the default gamepad buttons.
+        if self.version == 5 {
+            self.bindings.add_defaults(|binding| matches!(binding, Binding::Gamepad(_)));
+        }
+
+        // Version 7 added touch screens.
+        if (5..7).contains(&self.version) {
+            self.bindings.add_defaults(|binding| *binding == Binding::Touch);
+        }
+
+        self.version = SAVE_VERSION;
+        self
+    }
+}
+
+/// Where the save is read from and written to.
+#[derive(Resource, Debug, Clone)]
+pub struct SaveFile {
+    path: PathBuf,
+    legacy_path: PathBuf,
+}
+
+impl SaveFile {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        SaveFile {
+            path: path.into(),
+            legacy_path: PathBuf::from(LEGACY_HIGH_SCORE_PATH),
+        }
+    }
+
+    /// The save in the platform data directory, or in the working
+    /// directory when the platform doesn't have one.
+    pub fn default_location() -> Self {
+        let directory = ProjectDirs::from("", "", "flappy-qube")
+            .map(|dirs| dirs.data_dir().to_path_buf())
+            .unwrap_or_default();
+
+        SaveFile::new(directory.join(SAVE_FILE_NAME))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Loads the save, falling back to the old high score file and then
+    /// to defaults. Never fails, problems are logged as warnings.
+    pub fn load(&self) -> SaveData {
+        match self.read() {
+            Ok(Some(save_data)) => return save_data.migrate(),
+            Ok(None) => (),
+            Err(error) => {
+                warn!("Save at {} is unreadable, starting from defaults: {error}", self.path.display());
+                self.move_aside();
+                return SaveData::default();
+            }
+        }
+
+        let Some(save_data) = self.read_legacy() else {
+            return SaveData::default();
+        };
+        let save_data = save_data.migrate();
+
+        info!("Migrated {} into {}", self.legacy_path.display(), self.path.display());
+        if let Err(error) = self.save(&save_data) {
+            warn!("Couldn't write migrated save to {}: {error}", self.path.display());
+        }
+
+        save_data
+    }
+
+    /// Replaces the save with `save_data` in one step.
+    pub fn save(&self, save_data: &SaveData) -> Result<(), SaveError> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let text = ron::ser::to_string_pretty(save_data, ron::ser::PrettyConfig::default())?;
+
+        let temporary_path = self.path.with_extension("ron.tmp");
+        let mut file = File::create(&temporary_path)?;
+        file.write_all(text.as_bytes())?;
+        file.sync_all()?;
+
+        fs::rename(&temporary_path, &self.path)?;
+
+        Ok(())
+    }
+
+    fn read(&self) -> Result<Option<SaveData>, SaveError> {
+        let text = match fs::read_to_string(&self.path) {
+            Ok(text) => text,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error.into()),
+        };
+
+        Ok(Some(ron::from_str(&text)?))
+    }
+
+    // Version 0 of the save, a bare integer high score.
+    fn read_legacy(&self) -> Option<SaveData> {
+        let text = fs::read_to_string(&self.legacy_path).ok()?;
+
+        match text.trim().parse() {
+            Ok(high_score) => Some(SaveData {
+                version: 0,
+                high_score,
+                ..default()
+            }),
+            Err(error) => {
+                warn!("Ignoring unreadable {}: {error}", self.legacy_path.display());
+                None
+            }
+        }
+    }
+
+    // Keeps an unreadable save around for bug reports instead of
+    // overwriting it on the next save.
+    fn move_aside(&self) {
+        let corrupt_path = self.path.with_extension("ron.corrupt");
+
+        if let Err(error) = fs::rename(&self.path, &corrupt_path) {
+            warn!("Couldn't move unreadable save to {}: {error}", corrupt_path.display());
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),
+    Serialize(ron::Error),
+    Deserialize(ron::error::SpannedError),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(error) => write!(f, "{error}"),
+            SaveError::Serialize(error) => write!(f, "couldn't serialize save: {error}"),
+            SaveError::Deserialize(error) => write!(f, "couldn't parse save: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl From<io::Error> for SaveError {
+    fn from(error: io::Error) -> Self {
+        SaveError::Io(error)
+    }
+}
+
+impl From<ron::Error> for SaveError {
+    fn from(error: ron::Error) -> Self {
+        SaveError::Serialize(error)
+    }
+}
+
+impl From<ron::error::SpannedError> for SaveError {
+    fn from(error: ron::error::SpannedError) -> Self {
+        SaveError::Deserialize(error)
+    }
+}
+
+// Loads the save before anything else reads it
+pub fn load_save(
+    mut commands: Commands,
+) {
+    let save_file = SaveFile::default_location();
+    let save_data = save_file.load();
+
+    commands.insert_resource(save_data);
+    commands.insert_resource(save_file);
+}
+
+// Writes the save whenever it changes
+pub fn write_save(
+    save_file: Res<SaveFile>,
+    save_data: Res<SaveData>,
+) {
+    if !save_data.is_changed() || save_data.is_added() {
+        return;
+    }
+
+    if let Err(error) = save_file.save(&save_data) {
+        warn!("Couldn't save to {}: {error}", save_file.path().display());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A save file in an empty directory of its own, so tests running at
+    // the same time don't see each other's files.
+    fn save_file(name: &str) -> SaveFile {
+        let directory = std::env::temp_dir()
+            .join(format!("flappy-qube-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).expect("Should be able to create a temporary directory");
+
+        SaveFile {
+            path: directory.join(SAVE_FILE_NAME),
+            legacy_path: directory.join(LEGACY_HIGH_SCORE_PATH),
+        }
+    }
+
+    #[test]
+    fn version_1_save_is_migrated() {
+        let save_file = save_file("version-1");
+        fs::write(&save_file.path, "(version: 1, high_score: 42)").expect("Should be writable");
+
+        let save_data = save_file.load();
+
+        assert_eq!(save_data.version, SAVE_VERSION);
+        assert_eq!(save_data.high_score(Difficulty::Normal), 42);
+
+        let entries: Vec<_> = save_data.leaderboard.entries(DEFAULT_MODE).collect();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].name, UNKNOWN_NAME);
+        assert_eq!(entries[0].score, 42);
+        assert_eq!(save_data.bindings, InputBindings::default());
+    }
+
+    #[test]
+    fn corrupt_save_is_moved_aside() {
+        for (name, text) in [("truncated", "(version: 8, high_sc"), ("garbage", "\u{0}\u{7f}not a save")] {
+            let save_file = save_file(name);
+            fs::write(&save_file.path, text).expect("Should be writable");
+
+            assert_eq!(save_file.load(), SaveData::default());
+
+            let corrupt_path = save_file.path.with_extension("ron.corrupt");
+            assert_eq!(fs::read_to_string(corrupt_path).expect("Should be moved aside"), text);
+            assert!(!save_file.path.exists());
+        }
+    }
+
+    #[test]
+    fn legacy_high_score_is_imported() {
+        let save_file = save_file("legacy");
+        fs::write(&save_file.legacy_path, "17\n").expect("Should be writable");
+
+        let save_data = save_file.load();
+
+        assert_eq!(save_data.high_score(Difficulty::Normal), 17);
+        assert_eq!(save_data.leaderboard.entries(DEFAULT_MODE).count(), 1);
+
+        // The migrated save is written straight away.
+        let written = save_file
+            .read()
+            .expect("Should be readable")
+            .expect("Should be written");
+        assert_eq!(written.high_scores, save_data.high_scores);
+        assert_eq!(written.leaderboard, save_data.leaderboard);
+    }
+}
@@ -0,0 +1,123 @@
+//! Lifetime player statistics kept in the save.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+
+use super::{
+    AppState,
+    bird::BirdJumpEvent,
+    bird::BirdPointEvent,
+    bird::DeathCause,
+    game_over::{GameOverEvent, UnrankedRun},
+    replay::ReplayPlayback,
+    save::SaveData,
+    tuning::Tuning,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct Stats {
+    pub games_played: u64,
+    pub jumps: u64,
+    pub points: u64,
+    pub total_score: i128,
+    pub seconds_in_game: f64,
+    pub deaths: DeathCounts,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct DeathCounts {
+    pub pipe: u64,
+    pub ceiling: u64,
+    pub floor: u64,
+}
+
+impl DeathCounts {
+    pub fn add(&mut self, cause: DeathCause) {
+        match cause {
+            DeathCause::Pipe => self.pipe += 1,
+            DeathCause::Ceiling => self.ceiling += 1,
+            DeathCause::Floor => self.floor += 1,
+        }
+    }
+}
+
+impl Stats {
+    pub fn average_score(&self) -> f64 {
+        if self.games_played == 0 {
+            return 0.;
+        }
+
+        self.total_score as f64 / self.games_played as f64
+    }
+
+    pub fn time_in_game(&self) -> Duration {
+        Duration::from_secs_f64(self.seconds_in_game)
+    }
+}
+
+// Components, Resources, Events
+
+/// Counts for the run in progress. Only added to the save once the run
+/// is over so the save isn't rewritten on every jump.
+#[derive(Resource, Default, Debug)]
+pub struct RunStats {
+    pub jumps: u64,
+    pub points: u64,
+}
+
+// Runs left from the pause menu never send a game over, their counts
+// would otherwise end up in the next run
+pub fn reset_run_stats(
+    mut run_stats: ResMut<RunStats>,
+) {
+    *run_stats = RunStats::default();
+}
+
+// Counts jumps and points of the current run. The bird also jumps by
+// itself outside of runs, those jumps are dropped.
+pub fn count_run_stats(
+    mut jump_events: EventReader<BirdJumpEvent>,
+    mut point_events: EventReader<BirdPointEvent>,
+    mut run_stats: ResMut<RunStats>,
+    state: Res<State<AppState>>,
+) {
+    if state.0 != AppState::InGame {
+        jump_events.clear();
+        point_events.clear();
+        return;
+    }
+
+    run_stats.jumps += jump_events.iter().count() as u64;
+    run_stats.points += point_events.iter().count() as u64;
+}
+
+// Adds the finished run to the lifetime stats
+pub fn record_run(
+    mut game_over_event: EventReader<GameOverEvent>,
+    mut run_stats: ResMut<RunStats>,
+    mut save_data: ResMut<SaveData>,
+    playback: Option<Res<ReplayPlayback>>,
+    unranked: Option<Res<UnrankedRun>>,
+    tuning: Res<Tuning>,
+) {
+    for game_over in game_over_event.iter() {
+        let run_stats = std::mem::take(&mut *run_stats);
+
+        // Watching a replay isn't playing.
+        if playback.is_some() || unranked.is_some() {
+            continue;
+        }
+
+        let stats = &mut save_data.stats;
+        stats.games_played += 1;
+        stats.jumps += run_stats.jumps;
+        stats.points += run_stats.points;
+        stats.total_score += game_over.score;
+        stats.seconds_in_game += game_over.ticks as f64 * tuning.time_step as f64;
+        stats.deaths.add(game_over.cause);
+    }
+}
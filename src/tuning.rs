@@ -0,0 +1,380 @@
+//! Physics and course values read from `assets/game.tuning.ron`.
+//!
+//! The file is read once at startup and again whenever it changes while
+//! the game runs, so values can be tried without recompiling. The file
+//! holds the Normal difficulty, see [`crate::difficulty`] for the rest.
+
+use std::{
+    fs,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use bevy::{
+    prelude::*,
+    asset::{AssetLoader, FileAssetIo, LoadContext, LoadedAsset},
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
+use serde::{Serialize, Deserialize};
+
+use super::pipes::PIPE_X_SIZE;
+use super::replay::ReplayPlayback;
+
+// Constants
+
+// Relative to the assets folder.
+pub const TUNING_ASSET: &str = "game.tuning.ron";
+
+// Shortest tick allowed. Shorter ones would take longer to simulate
+// than they last.
+const MIN_TIME_STEP: f32 = 0.001;
+
+/// Every value that changes how the game plays. Distances are in world
+/// units and speeds in units per second, so `time_step` only changes how
+/// often the game is stepped and not how it plays.
+///
+/// As a resource this is what the game plays with, the tuning file with
+/// the difficulty applied.
+#[derive(Resource, Serialize, Deserialize, TypeUuid, Debug, Clone, PartialEq)]
+#[uuid = "5b0a4c3e-8f51-4d3a-9a4e-2c7f3b1d6e90"]
+#[serde(default)]
+pub struct Tuning {
+    /// Seconds per fixed tick.
+    pub time_step: f32,
+    /// Units per second, per second.
+    pub gravity: f32,
+    pub bird_jump: f32,
+    /// Lowest vertical velocity gravity can pull the bird to.
+    pub gravity_cap: f32,
+    /// The bird can't flap again while rising faster than this.
+    pub speed_cap: f32,
+    /// Horizontal distance between columns.
+    pub pipe_gap_x: f32,
+    /// Height of the opening between the top and bottom pipe.
+    pub pipe_gap_y: f32,
+    pub pipe_height_range: RangeInclusive<i32>,
+    /// Largest height difference between one column and the next.
+    pub pipe_height_jump: f32,
+    pub pipe_speed: f32,
+    /// How far the bird can overlap a pipe without dying.
+    pub hitbox_leniency: f32,
+    pub ramp: Ramp,
+}
+
+/// How the course gets harder as the score goes up.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Ramp {
+    /// Score the ramp is at full strength from.
+    pub full_at_score: f32,
+    /// Shape of the curve. 1 is a straight line, higher values stay easy
+    /// for longer and lower values get hard sooner.
+    pub exponent: f32,
+    /// Multipliers of the tuning values once the ramp is at full strength.
+    pub pipe_speed: f32,
+    pub pipe_gap_y: f32,
+    pub pipe_height_jump: f32,
+}
+
+impl Default for Ramp {
+    fn default() -> Self {
+        Ramp {
+            full_at_score: 100.,
+            exponent: 1.,
+            pipe_speed: 1.5,
+            pipe_gap_y: 0.7,
+            pipe_height_jump: 2.,
+        }
+    }
+}
+
+/// Course values for a point in a run, with the ramp applied.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CourseTuning {
+    pub pipe_speed: f32,
+    pub pipe_gap_y: f32,
+    pub pipe_height_jump: f32,
+}
+
+impl Default for Tuning {
+    fn default() -> Self {
+        Tuning {
+            time_step: 1. / 60.,
+            gravity: -2400.,
+            bird_jump: 800.,
+            gravity_cap: -4200.,
+            speed_cap: 1500.,
+            pipe_gap_x: 500.,
+            pipe_gap_y: 250.,
+            pipe_height_range: 200..=700,
+            pipe_height_jump: 250.,
+            pipe_speed: -300.,
+            hitbox_leniency: 16.,
+            ramp: Ramp::default(),
+        }
+    }
+}
+
+impl Tuning {
+    /// Reads the tuning file straight from disk, for use before the
+    /// asset server exists.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, TuningError> {
+        let text = fs::read_to_string(path)?;
+        let tuning: Tuning = ron::from_str(&text)?;
+        tuning.validate()?;
+
+        Ok(tuning)
+    }
+
+    /// Checks for values the game can't run with, like ticks that never
+    /// end or columns that overlap.
+    pub fn validate(&self) -> Result<(), TuningError> {
+        if !self.time_step.is_finite() || self.time_step < MIN_TIME_STEP {
+            return Err(TuningError::Invalid(format!(
+                "time_step should be at least {MIN_TIME_STEP}, is {}",
+                self.time_step,
+            )));
+        }
+
+        if !self.pipe_gap_x.is_finite() || self.pipe_gap_x <= PIPE_X_SIZE {
+            return Err(TuningError::Invalid(format!(
+                "pipe_gap_x should be more than the pipe width of {PIPE_X_SIZE}, is {}",
+                self.pipe_gap_x,
+            )));
+        }
+
+        if !self.pipe_gap_y.is_finite() || self.pipe_gap_y <= 0. {
+            return Err(TuningError::Invalid(format!(
+                "pipe_gap_y should be more than 0, is {}",
+                self.pipe_gap_y,
+            )));
+        }
+
+        // Heights are rolled from it, an empty range has nothing to roll.
+        if self.pipe_height_range.is_empty() {
+            return Err(TuningError::Invalid(format!(
+                "pipe_height_range should start at or below its end, is {:?}",
+                self.pipe_height_range,
+            )));
+        }
+
+        if !self.ramp.full_at_score.is_finite() {
+            return Err(TuningError::Invalid(format!(
+                "ramp.full_at_score should be a number, is {}",
+                self.ramp.full_at_score,
+            )));
+        }
+
+        let ramp_values = [
+            ("exponent", self.ramp.exponent),
+            ("pipe_speed", self.ramp.pipe_speed),
+            ("pipe_gap_y", self.ramp.pipe_gap_y),
+            ("pipe_height_jump", self.ramp.pipe_height_jump),
+        ];
+        for (name, value) in ramp_values {
+            if !value.is_finite() || value <= 0. {
+                return Err(TuningError::Invalid(format!(
+                    "ramp.{name} should be more than 0, is {value}",
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Where the asset server looks for the tuning file.
+    pub fn default_path() -> PathBuf {
+        FileAssetIo::get_base_path()
+            .join("assets")
+            .join(TUNING_ASSET)
+    }
+
+    pub fn fixed_period(&self) -> Duration {
+        Duration::from_secs_f32(self.time_step)
+    }
+
+    /// How far into the ramp a run at `score` is, from 0 to 1.
+    pub fn ramp_progress(&self, score: i128) -> f32 {
+        if self.ramp.full_at_score <= 0. {
+            return 1.;
+        }
+
+        (score as f32 / self.ramp.full_at_score)
+            .clamp(0., 1.)
+            .powf(self.ramp.exponent)
+    }
+
+    /// The course as it should be at `score`.
+    pub fn course_at(&self, score: i128) -> CourseTuning {
+        let progress = self.ramp_progress(score);
+        let ramp = |multiplier: f32| 1. + (multiplier - 1.) * progress;
+
+        CourseTuning {
+            pipe_speed: self.pipe_speed * ramp(self.ramp.pipe_speed),
+            pipe_gap_y: self.pipe_gap_y * ramp(self.ramp.pipe_gap_y),
+            pipe_height_jump: self.pipe_height_jump * ramp(self.ramp.pipe_height_jump),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum TuningError {
+    Io(std::io::Error),
+    Deserialize(ron::error::SpannedError),
+    Invalid(String),
+}
+
+impl std::fmt::Display for TuningError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TuningError::Io(error) => write!(f, "{error}"),
+            TuningError::Deserialize(error) => write!(f, "{error}"),
+            TuningError::Invalid(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for TuningError {}
+
+impl From<std::io::Error> for TuningError {
+    fn from(error: std::io::Error) -> Self {
+        TuningError::Io(error)
+    }
+}
+
+impl From<ron::error::SpannedError> for TuningError {
+    fn from(error: ron::error::SpannedError) -> Self {
+        TuningError::Deserialize(error)
+    }
+}
+
+#[derive(Default)]
+pub struct TuningLoader;
+
+impl AssetLoader for TuningLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let tuning: Tuning = ron::de::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(tuning));
+
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["tuning.ron"]
+    }
+}
+
+// Components, Resources, Events
+
+/// The tuning file as it was last read.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct BaseTuning(pub Tuning);
+
+// Keeps the tuning asset loaded so the asset server watches it.
+#[derive(Resource)]
+pub struct TuningHandle(pub Handle<Tuning>);
+
+// Initial Setup
+pub fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+) {
+    commands.insert_resource(TuningHandle(asset_server.load(TUNING_ASSET)));
+}
+
+// Copies the tuning file into the game whenever it is saved
+pub fn reload_tuning(
+    mut asset_events: EventReader<AssetEvent<Tuning>>,
+    assets: Res<Assets<Tuning>>,
+    handle: Res<TuningHandle>,
+    mut base_tuning: ResMut<BaseTuning>,
+    mut playback: Option<ResMut<ReplayPlayback>>,
+) {
+    for event in asset_events.iter() {
+        let AssetEvent::Modified { handle: modified } = event else {
+            continue;
+        };
+
+        if *modified != handle.0 {
+            continue;
+        }
+
+        let Some(reloaded) = assets.get(modified) else {
+            continue;
+        };
+
+        // Keeps playing with the last good values until the file is fixed.
+        if let Err(error) = reloaded.validate() {
+            warn!("Ignored {}: {error}", TUNING_ASSET);
+            continue;
+        }
+
+        // A replay plays with its own tuning, edits wait until it is over.
+        let current = match playback.as_deref_mut() {
+            Some(playback) => &mut playback.file_tuning,
+            None => &mut base_tuning.0,
+        };
+
+        if reloaded != current {
+            info!("Reloaded {}", TUNING_ASSET);
+            *current = reloaded.clone();
+        }
+    }
+}
+
+// Keeps the length of a fixed tick in line with the tuning
+pub fn apply_time_step(
+    tuning: Res<Tuning>,
+    mut fixed_time: ResMut<FixedTime>,
+) {
+    fixed_time.period = tuning.fixed_period();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_tuning_is_valid() {
+        assert!(Tuning::default().validate().is_ok());
+    }
+
+    #[test]
+    fn unplayable_values_are_rejected() {
+        let broken = [
+            Tuning { time_step: 0., ..default() },
+            Tuning { time_step: f32::NAN, ..default() },
+            Tuning { pipe_gap_x: PIPE_X_SIZE, ..default() },
+            Tuning { pipe_gap_y: 0., ..default() },
+            Tuning { pipe_gap_y: f32::INFINITY, ..default() },
+            Tuning { pipe_height_range: RangeInclusive::new(700, 200), ..default() },
+            Tuning { ramp: Ramp { full_at_score: f32::NAN, ..default() }, ..default() },
+            Tuning { ramp: Ramp { exponent: 0., ..default() }, ..default() },
+            Tuning { ramp: Ramp { pipe_speed: -1., ..default() }, ..default() },
+            Tuning { ramp: Ramp { pipe_gap_y: f32::NAN, ..default() }, ..default() },
+            Tuning { ramp: Ramp { pipe_height_jump: 0., ..default() }, ..default() },
+        ];
+
+        for tuning in broken {
+            assert!(tuning.validate().is_err(), "{tuning:?}");
+        }
+    }
+
+    #[test]
+    fn single_height_range_is_allowed() {
+        let tuning = Tuning {
+            pipe_height_range: 450..=450,
+            ..default()
+        };
+
+        assert!(tuning.validate().is_ok());
+    }
+}
@@ -0,0 +1,147 @@
+//! Ticks long enough for a pipe to move further than the bird and the
+//! pipe are wide together, which a check of where things end up misses.
+
+use bevy::prelude::*;
+
+use flappy_qube::{
+    AppState,
+    SimulationPlugin,
+    Velocity,
+    bird::{Bird, DeathCause},
+    game_over::GameOverEvent,
+    headless::Simulation,
+    pipes::{Bottom, NumberOf, Pipe, PointMarker},
+    tuning::Tuning,
+};
+
+// A whole second per tick, the pipes move 300 units each one. Without
+// gravity the bird only moves when a test makes it.
+fn large_time_step() -> Tuning {
+    Tuning {
+        time_step: 1.,
+        gravity: 0.,
+        ..default()
+    }
+}
+
+// Starts a run with the bird floating still at the middle of the screen.
+fn start_run(tuning: Tuning) -> Simulation {
+    let mut simulation = Simulation::from_plugin(SimulationPlugin {
+        seed: Some(1),
+        tuning,
+        ..default()
+    });
+
+    // A flap would carry the bird off the top of the screen in one of
+    // these ticks, so the run is started without one.
+    let world = simulation.world_mut();
+    let (mut transform, mut velocity) = world
+        .query_filtered::<(&mut Transform, &mut Velocity), With<Bird>>()
+        .single_mut(world);
+    transform.translation.y = 0.;
+    velocity.0 = Vec2::ZERO;
+
+    world
+        .resource_mut::<NextState<AppState>>()
+        .set(AppState::InGame);
+    simulation.step(false);
+    assert_eq!(simulation.state(), AppState::InGame);
+
+    simulation
+}
+
+// Moves the first column so `part` of it is at `x` and puts the bird at
+// the same height as that part. Returns the height.
+fn line_up_first_column<F: bevy::ecs::query::ReadOnlyWorldQuery>(
+    simulation: &mut Simulation,
+    x: f32,
+) -> f32 {
+    let world = simulation.world_mut();
+
+    let (part_x, part_y) = world
+        .query_filtered::<(&Transform, &NumberOf), F>()
+        .iter(world)
+        .find(|(_, number)| number.0 == 1)
+        .map(|(transform, _)| (transform.translation.x, transform.translation.y))
+        .expect("Should be a first column");
+
+    for (mut transform, number) in world.query::<(&mut Transform, &NumberOf)>().iter_mut(world) {
+        if number.0 == 1 {
+            transform.translation.x += x - part_x;
+        }
+    }
+
+    world
+        .query_filtered::<&mut Transform, With<Bird>>()
+        .single_mut(world)
+        .translation
+        .y = part_y;
+
+    part_y
+}
+
+fn death_cause(simulation: &Simulation) -> Option<DeathCause> {
+    let events = simulation.world().resource::<Events<GameOverEvent>>();
+
+    events
+        .get_reader()
+        .iter(events)
+        .map(|event| event.cause)
+        .last()
+}
+
+#[test]
+fn pipe_passing_the_bird_in_one_tick_hits_it() {
+    let mut simulation = start_run(large_time_step());
+
+    // Starts just in front of the bird and ends well behind it.
+    line_up_first_column::<(With<Pipe>, Without<Bottom>)>(&mut simulation, 60.);
+    simulation.step(false);
+
+    assert_eq!(death_cause(&simulation), Some(DeathCause::Pipe));
+}
+
+#[test]
+fn point_marker_passing_the_bird_in_one_tick_scores() {
+    let mut simulation = start_run(large_time_step());
+
+    line_up_first_column::<With<PointMarker>>(&mut simulation, 60.);
+    simulation.step(false);
+
+    assert_eq!(simulation.score(), 1);
+    assert_eq!(death_cause(&simulation), None);
+}
+
+#[test]
+fn point_marker_scores_once() {
+    let mut simulation = start_run(large_time_step());
+
+    line_up_first_column::<With<PointMarker>>(&mut simulation, 60.);
+    simulation.step(false);
+    simulation.step(false);
+
+    assert_eq!(simulation.score(), 1);
+}
+
+#[test]
+fn falling_through_a_pipe_in_one_tick_hits_it() {
+    let mut simulation = start_run(Tuning {
+        pipe_speed: 0.,
+        ..large_time_step()
+    });
+
+    // Starts in the gap and falls through the bottom pipe and out the
+    // other side within the tick, while the pipe stays put.
+    let gap_y = line_up_first_column::<With<PointMarker>>(&mut simulation, 0.);
+
+    let world = simulation.world_mut();
+    let (mut transform, mut velocity) = world
+        .query_filtered::<(&mut Transform, &mut Velocity), With<Bird>>()
+        .single_mut(world);
+    transform.translation.y = gap_y;
+    velocity.0 = Vec2::new(0., -2000.);
+
+    simulation.step(false);
+
+    assert_eq!(death_cause(&simulation), Some(DeathCause::Pipe));
+}
@@ -0,0 +1,103 @@
+use bevy::{
+    input::{
+        gamepad::{
+            GamepadButtonChangedEvent,
+            GamepadConnection,
+            GamepadConnectionEvent,
+            GamepadEvent,
+            GamepadInfo,
+        },
+        InputPlugin,
+        InputSystem,
+    },
+    prelude::*,
+};
+
+use flappy_qube::input::{self, Action, ActionInput, Binding, InputBindings};
+
+// Just enough of the game to turn gamepad events into actions.
+fn action_app() -> App {
+    let mut app = App::new();
+    app
+        .add_plugins(MinimalPlugins)
+        .add_plugin(InputPlugin)
+        .init_resource::<InputBindings>()
+        .init_resource::<ActionInput>()
+        .add_system(input::update_actions
+            .in_base_set(CoreSet::PreUpdate)
+            .after(InputSystem)
+        );
+
+    app
+}
+
+fn press(app: &mut App, gamepad: Gamepad, button_type: GamepadButtonType, value: f32) {
+    app.world.send_event(GamepadEvent::Button(GamepadButtonChangedEvent::new(gamepad, button_type, value)));
+    app.update();
+}
+
+#[test]
+fn default_bindings_have_no_conflicts() {
+    let bindings = InputBindings::default();
+
+    for action in Action::ALL {
+        for binding in bindings.get(action) {
+            assert_eq!(bindings.conflict(action, *binding), None, "{action:?} {binding:?}");
+        }
+    }
+}
+
+#[test]
+fn binding_used_on_the_same_screen_is_refused() {
+    let mut bindings = InputBindings::default();
+
+    // Flap and Leaderboard are both read in the main menu.
+    assert_eq!(
+        bindings.toggle(Action::Flap, Binding::Key(KeyCode::L)),
+        Err(Action::Leaderboard),
+    );
+    assert!(!bindings.flap.contains(&Binding::Key(KeyCode::L)));
+}
+
+#[test]
+fn binding_used_on_other_screens_is_allowed() {
+    let mut bindings = InputBindings::default();
+
+    // Reset Controls is only read on the settings screen.
+    assert_eq!(bindings.toggle(Action::Flap, Binding::Key(KeyCode::R)), Ok(()));
+    assert!(bindings.flap.contains(&Binding::Key(KeyCode::R)));
+
+    // Unbinding is always allowed.
+    assert_eq!(bindings.toggle(Action::Flap, Binding::Key(KeyCode::R)), Ok(()));
+    assert!(!bindings.flap.contains(&Binding::Key(KeyCode::R)));
+}
+
+#[test]
+fn gamepad_buttons_fire_their_actions() {
+    let mut app = action_app();
+    let gamepad = Gamepad::new(0);
+
+    app.world.send_event(GamepadEvent::Connection(GamepadConnectionEvent::new(
+        gamepad,
+        GamepadConnection::Connected(GamepadInfo { name: "Test Pad".to_string() }),
+    )));
+    app.update();
+
+    // South is both Flap and Confirm by default.
+    press(&mut app, gamepad, GamepadButtonType::South, 1.);
+    let action_input = app.world.resource::<ActionInput>();
+    assert!(action_input.just_pressed(Action::Flap));
+    assert!(action_input.just_pressed(Action::Confirm));
+    assert!(!action_input.just_pressed(Action::Back));
+
+    // Held down it stays pressed without being pressed again.
+    app.update();
+    let action_input = app.world.resource::<ActionInput>();
+    assert!(action_input.pressed(Action::Flap));
+    assert!(!action_input.just_pressed(Action::Flap));
+
+    press(&mut app, gamepad, GamepadButtonType::South, 0.);
+    let action_input = app.world.resource::<ActionInput>();
+    assert!(!action_input.pressed(Action::Flap));
+    assert!(!action_input.pressed(Action::Confirm));
+}
@@ -0,0 +1,100 @@
+//! Replays have to end the same however the tuning file has changed
+//! since they were recorded.
+
+use bevy::prelude::default;
+
+use flappy_qube::{
+    AppState,
+    SimulationPlugin,
+    headless::{self, Simulation},
+    replay::{Replay, ReplayPlayback},
+    tuning::{BaseTuning, Tuning},
+};
+
+// Different enough from the defaults that the run goes differently.
+fn edited_tuning() -> Tuning {
+    Tuning {
+        gravity: -2000.,
+        pipe_gap_x: 600.,
+        ..default()
+    }
+}
+
+// Plays one run with the autopilot and returns its replay.
+fn record_run(tuning: Tuning) -> Replay {
+    let mut simulation = Simulation::from_plugin(SimulationPlugin {
+        seed: Some(5),
+        tuning,
+        ..default()
+    });
+
+    while simulation.state() != AppState::GameOver {
+        let flap = simulation.autopilot();
+        simulation.step(flap);
+    }
+
+    simulation.replay().clone()
+}
+
+#[test]
+fn replay_round_trips_through_text() {
+    let replay = record_run(edited_tuning());
+    let parsed: Replay = replay
+        .to_string()
+        .parse()
+        .expect("Should be a valid replay");
+
+    assert_eq!(parsed, replay);
+    assert_eq!(parsed.tuning, Some(edited_tuning()));
+}
+
+#[test]
+fn replay_plays_with_its_own_tuning() {
+    let replay = record_run(edited_tuning());
+    let recorded = replay.score;
+
+    // The tuning file has gone back to the defaults since.
+    let score = headless::play_replay(SimulationPlugin {
+        replay: Some(replay),
+        tuning: Tuning::default(),
+        ..default()
+    });
+
+    assert_eq!(score, recorded);
+}
+
+#[test]
+fn replay_with_a_broken_tuning_is_refused() {
+    let mut replay = record_run(Tuning::default());
+    replay.tuning = Some(Tuning {
+        time_step: 0.,
+        ..default()
+    });
+
+    assert!(replay.to_string().parse::<Replay>().is_err());
+}
+
+#[test]
+fn runs_after_a_replay_go_back_to_the_tuning_file_and_seed() {
+    let replay = record_run(edited_tuning());
+    let seed = replay.seed;
+
+    let mut simulation = Simulation::from_plugin(SimulationPlugin {
+        replay: Some(replay),
+        tuning: Tuning::default(),
+        ..default()
+    });
+
+    simulation.step(false);
+    while simulation.state() == AppState::InGame {
+        simulation.step(false);
+    }
+
+    let world = simulation.world();
+    assert!(world.get_resource::<ReplayPlayback>().is_none());
+    assert_eq!(world.resource::<BaseTuning>().0, Tuning::default());
+
+    // No seed was picked, so the next runs are random again instead of
+    // replaying the same course.
+    assert_ne!(simulation.seed(), seed);
+}